@@ -0,0 +1,70 @@
+//! Measures cost of looking up keys in documents of growing size.
+//!
+//! Run with `cargo bench --bench lookup`. Two access patterns are measured:
+//!
+//! - reading the same few hundred keys (spread over whole document),
+//!   as when a program reads its settings from a large generated file;
+//!   time per lookup stays the same regardless of number of keys,
+//!   which is checked at the end of the run,
+//! - reading keys at random from whole document; time per lookup grows
+//!   with document size, as the index and pairs of big documents no longer
+//!   fit in CPU caches, so most lookups wait for main memory. Number
+//!   of steps of each lookup stays the same (there is no scanning),
+//!   only their cost grows.
+
+use std::{hint::black_box, time::Instant};
+
+use cfua::Cfua;
+
+const LOOKUPS: usize = 100_000;
+/// Number of distinct keys read in "few hundred keys" pattern.
+const WORKING_SET: usize = 256;
+const SIZES: [usize; 4] = [1_000, 10_000, 100_000, 1_000_000];
+
+/// Generates key consisting only of lowercase letters, as required by cfua.
+fn key(mut n: usize) -> String {
+    let mut key = String::from("key-");
+    loop {
+        key.push((b'a' + (n % 26) as u8) as char);
+        n /= 26;
+        if n == 0 {
+            break key;
+        }
+    }
+}
+
+/// Returns average time of looking up `keys` in `data`, in nanoseconds.
+fn measure(data: &Cfua, keys: &[String]) -> f64 {
+    let start = Instant::now();
+    for key in keys {
+        black_box(data.read_integer(black_box(key)));
+    }
+    start.elapsed().as_nanos() as f64 / keys.len() as f64
+}
+
+fn main() {
+    let mut working_set_times = Vec::new();
+    for size in SIZES {
+        let mut source = String::new();
+        for i in 0..size {
+            source.push_str(&format!("{}: {i}\n", key(i)));
+        }
+        let data = Cfua::from_string(source).unwrap();
+
+        let step = size / WORKING_SET;
+        let few: Vec<String> = (0..LOOKUPS).map(|i| key(i % WORKING_SET * step)).collect();
+        let random: Vec<String> = (0..LOOKUPS).map(|i| key(i * 7919 % size)).collect();
+        // warm up, so that working set is measured from caches
+        measure(&data, &few);
+
+        let few = measure(&data, &few);
+        let random = measure(&data, &random);
+        println!("{size:>8} keys: {few:>6.1} ns per lookup of {WORKING_SET} keys, {random:>6.1} ns per random lookup");
+        working_set_times.push(few);
+    }
+
+    // a lookup scanning pairs would be hundreds of times slower
+    // in the largest document; allow a lot of noise
+    let (first, last) = (working_set_times[0], working_set_times[SIZES.len() - 1]);
+    assert!(last < first * 3.0, "lookup cost grows with document size: {first:.1} ns -> {last:.1} ns");
+}
@@ -1,4 +1,4 @@
-use std::{fs::File, io::{self, Write}};
+use std::{fs::File, io::{self, BufWriter, Write}};
 
 use cfua::Cfua;
 
@@ -8,8 +8,8 @@ fn main() -> io::Result<()> {
     data.write_integer("example-number", 42);
     data.write_string("greeting", "Hello, world!");
 
-    let mut file = File::create_new("examples/output.cfua")?;
-    write!(&mut file, "{}", data.to_string())?;
+    let mut file = BufWriter::new(File::create_new("examples/output.cfua")?);
+    data.write_to(&mut file)?;
     file.flush()?;
     Ok(())
 }
\ No newline at end of file
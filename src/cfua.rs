@@ -1,12 +1,10 @@
 #![deny(unsafe_code)]
 
-use crate::array::ToCfuaArray;
+use std::{collections::HashMap, fmt, iter::FusedIterator, mem, slice};
+
+use crate::{array::ToCfuaArray, parser::is_valid_name, write::Radix, CfuaError};
 
-// type CfuaKV = HashMap<String, CfuaType>;
 type CfuaKV = Vec<(String, CfuaType)>;
-// pub(crate) struct CfuaKV {
-//     data: Vec<(String, CfuaType)>,
-// }
 
 /// Main library type representing cfua data.
 /// 
@@ -27,8 +25,9 @@ type CfuaKV = Vec<(String, CfuaType)>;
 /// ```
 /// 
 /// To further write data, use appropriate writing functions.
-/// After finishing, use [`to_string`] function to convert data into string,
-/// which can be, for example, written to file.
+/// After finishing, use [`to_string`] function (or `{}` formatting)
+/// to convert data into string, or [`write_to`] to write it directly
+/// into a file or any other [`std::io::Write`].
 /// 
 /// ```
 /// use cfua::Cfua;
@@ -43,23 +42,157 @@ type CfuaKV = Vec<(String, CfuaType)>;
 /// [`from_file_path`]: self::Cfua::from_file_path
 /// [`from_string`]: self::Cfua::from_string
 /// [`create`]: self::Cfua::create
-/// [`to_string`]: self::Cfua::to_string
-#[derive(Debug, Clone, PartialEq)]
+/// [`to_string`]: ToString::to_string
+/// [`write_to`]: self::Cfua::write_to
+#[derive(Clone)]
 pub struct Cfua {
+    /// key-value pairs, in order of their appearance
     data: CfuaKV,
+    /// position of first pair with given key in `data`,
+    /// used to look up values without scanning all pairs
+    index: HashMap<String, usize>,
+    /// comment placed before each pair in `data`, at the same position
+    comments: Vec<Option<String>>,
+    /// radix of integer literal (or array of them) of each pair in `data`,
+    /// at the same position
+    radixes: Vec<Radix>,
+}
+
+impl PartialEq for Cfua {
+    fn eq(&self, other: &Self) -> bool {
+        // index is derived from data, so there's no need to compare it;
+        // comments and radixes don't affect stored values
+        self.data == other.data
+    }
+}
+
+impl fmt::Debug for Cfua {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cfua").field("data", &self.data).finish()
+    }
 }
 
 /// An enum containing possible value types stored in cfua file.
+///
+/// The enum is non-exhaustive, as some variants (like `BigInt`)
+/// are available only with cargo features.
 #[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub enum CfuaType {
     Integer(i64),
+    /// An integer above `i64::MAX`. Smaller integers are always
+    /// stored as [`CfuaType::Integer`] by parser and conversions.
+    Unsigned(u64),
+    /// An integer outside of `i64` and `u64` range, available with
+    /// `bigint` feature. Without it, such integers are rejected by parser.
+    #[cfg(feature = "bigint")]
+    BigInt(num_bigint::BigInt),
     Float(f64),
     String(String),
     Bool(bool),
     Array(Vec<CfuaType>),
-    /// A section, as defined by `@` sign. Note that section's name
-    /// is stored as a value's key.
-    Section(()),
+    /// A section, as defined by `@` sign, holding all values declared
+    /// within it. Note that section's name is stored as a value's key.
+    Section(Cfua),
+}
+
+impl CfuaType {
+    /// Returns name of value's type, as used in error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            CfuaType::Integer(_) |
+            CfuaType::Unsigned(_) => "integer",
+            #[cfg(feature = "bigint")]
+            CfuaType::BigInt(_) => "integer",
+            CfuaType::Float(_) => "float",
+            CfuaType::String(_) => "string",
+            CfuaType::Bool(_) => "boolean",
+            CfuaType::Array(_) => "array",
+            CfuaType::Section(_) => "section",
+        }
+    }
+
+    /// Returns `true` if value is an integer of any size.
+    pub(crate) fn is_integer(&self) -> bool {
+        match self {
+            CfuaType::Integer(_) | CfuaType::Unsigned(_) => true,
+            #[cfg(feature = "bigint")]
+            CfuaType::BigInt(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if both values have the same type, treating
+    /// integers of any size as the same type.
+    pub(crate) fn has_same_type(&self, other: &CfuaType) -> bool {
+        (self.is_integer() && other.is_integer()) || mem::discriminant(self) == mem::discriminant(other)
+    }
+
+    /// Returns integer value, or `None` if value is not an integer.
+    pub fn as_integer(&self) -> Option<i64> {
+        if let CfuaType::Integer(i) = self {
+            Some(*i)
+        } else {
+            None
+        }
+    }
+
+    /// Returns non-negative integer value, or `None` if value is not
+    /// an integer or doesn't fit in `u64`.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            CfuaType::Integer(i) => u64::try_from(*i).ok(),
+            CfuaType::Unsigned(u) => Some(*u),
+            #[cfg(feature = "bigint")]
+            CfuaType::BigInt(i) => u64::try_from(i).ok(),
+            _ => None,
+        }
+    }
+
+    /// Returns float value, or `None` if value is not a float.
+    pub fn as_float(&self) -> Option<f64> {
+        if let CfuaType::Float(f) = self {
+            Some(*f)
+        } else {
+            None
+        }
+    }
+
+    /// Returns string value, or `None` if value is not a string.
+    pub fn as_str(&self) -> Option<&str> {
+        if let CfuaType::String(s) = self {
+            Some(s)
+        } else {
+            None
+        }
+    }
+
+    /// Returns boolean value, or `None` if value is not a boolean.
+    pub fn as_bool(&self) -> Option<bool> {
+        if let CfuaType::Bool(b) = self {
+            Some(*b)
+        } else {
+            None
+        }
+    }
+
+    /// Returns array elements, or `None` if value is not an array.
+    pub fn as_array(&self) -> Option<&[CfuaType]> {
+        if let CfuaType::Array(v) = self {
+            Some(v)
+        } else {
+            None
+        }
+    }
+
+    /// Returns section content, or `None` if value is not a section.
+    pub fn as_section(&self) -> Option<&Cfua> {
+        if let CfuaType::Section(s) = self {
+            Some(s)
+        } else {
+            None
+        }
+    }
 }
 
 impl Cfua {
@@ -67,37 +200,106 @@ impl Cfua {
     pub fn create() -> Self {
         Self {
             data: Vec::new(),
+            index: HashMap::new(),
+            comments: Vec::new(),
+            radixes: Vec::new(),
+        }
+    }
+
+    /// Rebuilds index after positions of pairs have changed.
+    fn reindex(&mut self) {
+        self.index.clear();
+        for (i, (key, _)) in self.data.iter().enumerate() {
+            if !self.index.contains_key(key) {
+                self.index.insert(key.clone(), i);
+            }
         }
     }
 
     /// Appends integer `value` with `key` into the end of structure.
     pub fn write_integer<K>(&mut self, key: K, value: i64)
     where K: ToString {
-        self.data.push((key.to_string(), CfuaType::Integer(value)));
+        self.write_value(key, CfuaType::Integer(value));
     }
 
     /// Appends float `value` with `key` into the end of structure.
     pub fn write_float<K>(&mut self, key: K, value: f64)
     where K: ToString {
-        self.data.push((key.to_string(), CfuaType::Float(value)));
+        self.write_value(key, CfuaType::Float(value));
     }
 
     /// Appends string `value` with `key` into the end of structure.
     pub fn write_string<S>(&mut self, key: S, value: S)
     where S: ToString {
-        self.data.push((key.to_string(), CfuaType::String(value.to_string())));
+        self.write_value(key, CfuaType::String(value.to_string()));
     }
 
     /// Appends boolean `value` with `key` into the end of structure.
     pub fn write_bool<K>(&mut self, key: K, value: bool)
     where K: ToString {
-        self.data.push((key.to_string(), CfuaType::Bool(value)));
+        self.write_value(key, CfuaType::Bool(value));
+    }
+
+    /// Appends any `value` with `key` into the end of structure.
+    pub(crate) fn write_value<K>(&mut self, key: K, value: CfuaType)
+    where K: ToString {
+        let key = key.to_string();
+        if !self.index.contains_key(&key) {
+            self.index.insert(key.clone(), self.data.len());
+        }
+        self.data.push((key, value));
+        self.comments.push(None);
+        self.radixes.push(Radix::Decimal);
     }
 
-    /// Appends section (`@key`) into the end of structure.
-    pub fn write_section<K>(&mut self, key: K)
+    /// Appends section (`@key`) with its content into the end of structure.
+    /// 
+    /// Sections are always written after top-level values, so they can be
+    /// read back unchanged. As cfua syntax does not support nesting,
+    /// returns [`CfuaError::NestedSection`] instead of appending `section`
+    /// which has sections itself.
+    pub fn write_section<K>(&mut self, key: K, section: Cfua) -> Result<(), CfuaError>
     where K: ToString {
-        self.data.push((key.to_string(), CfuaType::Section(())));
+        let section = CfuaType::Section(section);
+        check_nesting(&section)?;
+        self.write_value(key, section);
+        Ok(())
+    }
+
+    /// Attaches `comment` to value or section with `key` (the first one,
+    /// if the key is repeated), replacing existing one. The comment is
+    /// written before the key, as `%` lines, one for each line of `comment`.
+    /// It stays with the value when it is renamed or moved, and is dropped
+    /// when the value is removed.
+    ///
+    /// Returns [`CfuaError::KeyNotFound`] if there is no value with `key`.
+    pub fn write_comment<K, C>(&mut self, key: K, comment: C) -> Result<(), CfuaError>
+    where K: AsRef<str>,
+          C: ToString {
+        let key = key.as_ref();
+        let index = self.position(key).ok_or_else(|| CfuaError::KeyNotFound(key.to_string()))?;
+        self.comments[index] = Some(comment.to_string());
+        Ok(())
+    }
+
+    /// Returns comment attached to value or section with `key` (the first
+    /// one, if the key is repeated). Comments read from input have single
+    /// space following `%` removed, and consecutive comment lines are joined
+    /// with newlines. Comments which are not followed by a key or section
+    /// (e.g. at the end of input) are not kept.
+    pub fn read_comment<K>(&self, key: K) -> Option<&str>
+    where K: AsRef<str> {
+        self.position(key.as_ref()).and_then(|i| self.comment_at(i))
+    }
+
+    /// Returns comment attached to pair at `index`.
+    pub(crate) fn comment_at(&self, index: usize) -> Option<&str> {
+        self.comments[index].as_deref()
+    }
+
+    /// Replaces comment attached to pair at `index`.
+    pub(crate) fn set_comment_at(&mut self, index: usize, comment: Option<String>) {
+        self.comments[index] = comment;
     }
 
     /// Appends array into the end of structure. The `value` is constructed
@@ -105,25 +307,183 @@ impl Cfua {
     pub fn write_array<K, F>(&mut self, key: K, value: F)
     where K: ToString,
           F: ToCfuaArray {
-        self.data.push((key.to_string(), value.finish()));
+        self.write_value(key, value.finish());
+    }
+
+    /// Like [`write_integer`](Self::write_integer), but returns
+    /// [`CfuaError::InvalidKey`] instead of writing a `key` which
+    /// couldn't be read back.
+    pub fn try_write_integer<K>(&mut self, key: K, value: i64) -> Result<(), CfuaError>
+    where K: ToString {
+        self.try_write_value(key, CfuaType::Integer(value))
+    }
+
+    /// Like [`write_float`](Self::write_float), but returns
+    /// [`CfuaError::InvalidKey`] instead of writing a `key` which
+    /// couldn't be read back.
+    pub fn try_write_float<K>(&mut self, key: K, value: f64) -> Result<(), CfuaError>
+    where K: ToString {
+        self.try_write_value(key, CfuaType::Float(value))
+    }
+
+    /// Like [`write_string`](Self::write_string), but returns
+    /// [`CfuaError::InvalidKey`] instead of writing a `key` which
+    /// couldn't be read back.
+    pub fn try_write_string<S>(&mut self, key: S, value: S) -> Result<(), CfuaError>
+    where S: ToString {
+        self.try_write_value(key, CfuaType::String(value.to_string()))
+    }
+
+    /// Like [`write_bool`](Self::write_bool), but returns
+    /// [`CfuaError::InvalidKey`] instead of writing a `key` which
+    /// couldn't be read back.
+    pub fn try_write_bool<K>(&mut self, key: K, value: bool) -> Result<(), CfuaError>
+    where K: ToString {
+        self.try_write_value(key, CfuaType::Bool(value))
+    }
+
+    /// Like [`write_array`](Self::write_array), but returns
+    /// [`CfuaError::InvalidKey`] instead of writing a `key` which
+    /// couldn't be read back, and [`CfuaError::Conversion`] instead
+    /// of writing an empty array.
+    pub fn try_write_array<K, F>(&mut self, key: K, value: F) -> Result<(), CfuaError>
+    where K: ToString,
+          F: ToCfuaArray {
+        self.try_write_value(key, value.finish())
+    }
+
+    /// Like [`write_section`](Self::write_section), but returns
+    /// [`CfuaError::InvalidKey`] instead of writing a `key` which
+    /// couldn't be read back. Content of `section` is checked too,
+    /// and must not contain other sections.
+    pub fn try_write_section<K>(&mut self, key: K, section: Cfua) -> Result<(), CfuaError>
+    where K: ToString {
+        let key = key.to_string();
+        check_key(&key)?;
+        section.check(true).map_err(|e| e.at_key(&key))?;
+        self.write_value(key, CfuaType::Section(section));
+        Ok(())
+    }
+
+    pub(crate) fn try_write_value<K>(&mut self, key: K, value: CfuaType) -> Result<(), CfuaError>
+    where K: ToString {
+        let key = key.to_string();
+        check_key(&key)?;
+        if let CfuaType::Array(elements) = &value {
+            check_array(elements).map_err(|e| e.at_key(&key))?;
+        }
+        self.write_value(key, value);
+        Ok(())
+    }
+
+    /// Checks that all keys and section names (including ones inside
+    /// of sections) can be read back by parser, and that all values can
+    /// be written. Returns [`CfuaError::InvalidKey`] with the first
+    /// invalid name, [`CfuaError::Conversion`] with path of the first
+    /// value which cannot be written, e.g. an empty array, or
+    /// [`CfuaError::NestedSection`] for a section nested in other section.
+    pub fn validate(&self) -> Result<(), CfuaError> {
+        self.check(false)
+    }
+
+    /// Validates data, which is content of a section if `in_section`.
+    fn check(&self, in_section: bool) -> Result<(), CfuaError> {
+        for (key, value) in &self.data {
+            check_key(key)?;
+            match value {
+                CfuaType::Array(elements) => check_array(elements).map_err(|e| e.at_key(key))?,
+                CfuaType::Section(_) if in_section => return Err(CfuaError::NestedSection(key.clone())),
+                CfuaType::Section(section) => section.check(true).map_err(|e| e.at_key(key))?,
+                _ => {},
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns all key-value pairs, without copying them.
+    pub(crate) fn entries(&self) -> &[(String, CfuaType)] {
+        &self.data
+    }
+
+    /// Takes all key-value pairs, along with their comments and radixes.
+    pub(crate) fn into_entries(self) -> impl Iterator<Item = (String, CfuaType, Option<String>, Radix)> {
+        self.data.into_iter()
+            .zip(self.comments)
+            .zip(self.radixes)
+            .map(|(((key, value), comment), radix)| (key, value, comment, radix))
+    }
+
+    /// Returns position of first pair with `key`.
+    pub(crate) fn position(&self, key: &str) -> Option<usize> {
+        self.index.get(key).copied()
+    }
+
+    /// Inserts pair at `index`, shifting all following pairs.
+    pub(crate) fn insert_at(&mut self, index: usize, key: String, value: CfuaType) {
+        self.data.insert(index, (key, value));
+        self.comments.insert(index, None);
+        self.radixes.insert(index, Radix::Decimal);
+        self.reindex();
+    }
+
+    /// Removes pair at `index`, shifting all following pairs.
+    pub(crate) fn remove_at(&mut self, index: usize) -> (String, CfuaType) {
+        let pair = self.data.remove(index);
+        self.comments.remove(index);
+        self.radixes.remove(index);
+        self.reindex();
+        pair
+    }
+
+    /// Changes key of pair at `index`.
+    pub(crate) fn rename_at(&mut self, index: usize, key: String) {
+        self.data[index].0 = key;
+        self.reindex();
+    }
+
+    /// Returns radix in which integer of pair at `index` was read.
+    pub(crate) fn radix_at(&self, index: usize) -> Radix {
+        self.radixes[index]
+    }
+
+    /// Records radix in which integer of pair at `index` was read.
+    pub(crate) fn set_radix_at(&mut self, index: usize, radix: Radix) {
+        self.radixes[index] = radix;
+    }
+
+    /// Returns value of pair at `index` for modification.
+    pub(crate) fn value_at_mut(&mut self, index: usize) -> &mut CfuaType {
+        &mut self.data[index].1
+    }
+
+    /// Searches for value stored within `key`.
+    pub(crate) fn find(&self, key: &str) -> Option<&CfuaType> {
+        self.position(key).map(|i| &self.data[i].1)
     }
 
     /// Searches for integer stored within `key`
     /// and returns its value if found.
     pub fn read_integer<K>(&self, key: K) -> Option<i64>
-    where K: ToString {
-        if let Some((_, CfuaType::Integer(i))) = self.data.iter().find(|p| p.0 == key.to_string()) {
+    where K: AsRef<str> {
+        if let Some(CfuaType::Integer(i)) = self.find(key.as_ref()) {
             Some(*i)
         } else {
             None
         }
     }
 
+    /// Searches for non-negative integer stored within `key`
+    /// and returns its value if found and it fits in `u64`.
+    pub fn read_u64<K>(&self, key: K) -> Option<u64>
+    where K: AsRef<str> {
+        self.find(key.as_ref()).and_then(CfuaType::as_u64)
+    }
+
     /// Searches for float stored within `key`
     /// and returns its value if found.
     pub fn read_float<K>(&self, key: K) -> Option<f64>
-    where K: ToString {
-        if let Some((_, CfuaType::Float(f))) = self.data.iter().find(|p| p.0 == key.to_string()) {
+    where K: AsRef<str> {
+        if let Some(CfuaType::Float(f)) = self.find(key.as_ref()) {
             Some(*f)
         } else {
             None
@@ -133,8 +493,8 @@ impl Cfua {
     /// Searches for string stored within `key`
     /// and returns its value if found.
     pub fn read_string<K>(&self, key: K) -> Option<String>
-    where K: ToString {
-        if let Some((_, CfuaType::String(s))) = self.data.iter().find(|p| p.0 == key.to_string()) {
+    where K: AsRef<str> {
+        if let Some(CfuaType::String(s)) = self.find(key.as_ref()) {
             Some(s.clone())
         } else {
             None
@@ -144,8 +504,8 @@ impl Cfua {
     /// Searches for boolean stored within `key`
     /// and returns its value if found.
     pub fn read_bool<K>(&self, key: K) -> Option<bool>
-    where K: ToString {
-        if let Some((_, CfuaType::Bool(b))) = self.data.iter().find(|p| p.0 == key.to_string()) {
+    where K: AsRef<str> {
+        if let Some(CfuaType::Bool(b)) = self.find(key.as_ref()) {
             Some(*b)
         } else {
             None
@@ -155,20 +515,281 @@ impl Cfua {
     /// Searches for array stored within `key`
     /// and returns its copy as `Vec` if found.
     pub fn read_array<K>(&self, key: K) -> Option<Vec<CfuaType>>
-    where K: ToString {
-        if let Some((_, CfuaType::Array(v))) = self.data.iter().find(|p| p.0 == key.to_string()) {
+    where K: AsRef<str> {
+        if let Some(CfuaType::Array(v)) = self.find(key.as_ref()) {
             Some(v.clone())
         } else {
             None
         }
     }
 
+    /// Searches for section named `key`
+    /// and returns its copy if found.
+    pub fn read_section<K>(&self, key: K) -> Option<Cfua>
+    where K: AsRef<str> {
+        if let Some(CfuaType::Section(s)) = self.find(key.as_ref()) {
+            Some(s.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Searches for value stored within `key`, returning
+    /// [`CfuaError::KeyNotFound`] if there is none.
+    fn get_value(&self, key: &str) -> Result<&CfuaType, CfuaError> {
+        self.find(key).ok_or_else(|| CfuaError::KeyNotFound(key.to_string()))
+    }
+
+    /// Searches for integer stored within `key`. Unlike [`read_integer`],
+    /// returns an error telling whether key is missing or holds
+    /// a value of different type.
+    /// 
+    /// [`read_integer`]: Self::read_integer
+    pub fn get_integer<K>(&self, key: K) -> Result<i64, CfuaError>
+    where K: AsRef<str> {
+        let key = key.as_ref();
+        match self.get_value(key)? {
+            CfuaType::Integer(i) => Ok(*i),
+            other => Err(type_mismatch(key, "integer", other)),
+        }
+    }
+
+    /// Searches for non-negative integer stored within `key`. Unlike
+    /// [`read_u64`], returns an error telling whether key is missing,
+    /// holds a value of different type or a value out of `u64` range.
+    /// 
+    /// [`read_u64`]: Self::read_u64
+    pub fn get_u64<K>(&self, key: K) -> Result<u64, CfuaError>
+    where K: AsRef<str> {
+        self.get(key)
+    }
+
+    /// Searches for float stored within `key`. Unlike [`read_float`],
+    /// returns an error telling whether key is missing or holds
+    /// a value of different type.
+    /// 
+    /// [`read_float`]: Self::read_float
+    pub fn get_float<K>(&self, key: K) -> Result<f64, CfuaError>
+    where K: AsRef<str> {
+        let key = key.as_ref();
+        match self.get_value(key)? {
+            CfuaType::Float(f) => Ok(*f),
+            other => Err(type_mismatch(key, "float", other)),
+        }
+    }
+
+    /// Searches for string stored within `key`. Unlike [`read_string`],
+    /// returns an error telling whether key is missing or holds
+    /// a value of different type.
+    /// 
+    /// [`read_string`]: Self::read_string
+    pub fn get_string<K>(&self, key: K) -> Result<String, CfuaError>
+    where K: AsRef<str> {
+        let key = key.as_ref();
+        match self.get_value(key)? {
+            CfuaType::String(s) => Ok(s.clone()),
+            other => Err(type_mismatch(key, "string", other)),
+        }
+    }
+
+    /// Searches for boolean stored within `key`. Unlike [`read_bool`],
+    /// returns an error telling whether key is missing or holds
+    /// a value of different type.
+    /// 
+    /// [`read_bool`]: Self::read_bool
+    pub fn get_bool<K>(&self, key: K) -> Result<bool, CfuaError>
+    where K: AsRef<str> {
+        let key = key.as_ref();
+        match self.get_value(key)? {
+            CfuaType::Bool(b) => Ok(*b),
+            other => Err(type_mismatch(key, "boolean", other)),
+        }
+    }
+
+    /// Searches for array stored within `key`. Unlike [`read_array`],
+    /// returns an error telling whether key is missing or holds
+    /// a value of different type.
+    /// 
+    /// [`read_array`]: Self::read_array
+    pub fn get_array<K>(&self, key: K) -> Result<Vec<CfuaType>, CfuaError>
+    where K: AsRef<str> {
+        let key = key.as_ref();
+        match self.get_value(key)? {
+            CfuaType::Array(v) => Ok(v.clone()),
+            other => Err(type_mismatch(key, "array", other)),
+        }
+    }
+
+    /// Searches for section named `key`. Unlike [`read_section`],
+    /// returns an error telling whether key is missing or holds
+    /// a value of different type.
+    /// 
+    /// [`read_section`]: Self::read_section
+    pub fn get_section<K>(&self, key: K) -> Result<Cfua, CfuaError>
+    where K: AsRef<str> {
+        let key = key.as_ref();
+        match self.get_value(key)? {
+            CfuaType::Section(s) => Ok(s.clone()),
+            other => Err(type_mismatch(key, "section", other)),
+        }
+    }
+
+    /// Returns names of all sections, in order of their appearance.
+    pub fn sections(&self) -> Vec<String> {
+        self.data.iter()
+            .filter(|p| matches!(p.1, CfuaType::Section(_)))
+            .map(|p| p.0.clone())
+            .collect()
+    }
+
+    /// Returns paths of all non-section values, in order of their
+    /// appearance: `key` for top-level values, and `section.key`
+    /// for values inside a section, as accepted by [`query`](Self::query).
+    pub fn value_paths(&self) -> Vec<String> {
+        let mut paths = Vec::new();
+        for (key, value) in &self.data {
+            match value {
+                CfuaType::Section(section) => {
+                    paths.extend(section.iter().map(|(inner, _)| format!("{key}.{inner}")));
+                },
+                _ => paths.push(key.clone()),
+            }
+        }
+        paths
+    }
+
+    /// Searches for string stored within `key`
+    /// and returns reference to it if found.
+    pub fn read_str<K>(&self, key: K) -> Option<&str>
+    where K: AsRef<str> {
+        self.find(key.as_ref()).and_then(CfuaType::as_str)
+    }
+
+    /// Searches for array stored within `key`
+    /// and returns reference to its elements if found.
+    pub fn read_array_ref<K>(&self, key: K) -> Option<&[CfuaType]>
+    where K: AsRef<str> {
+        self.find(key.as_ref()).and_then(CfuaType::as_array)
+    }
+
+    /// Searches for section named `key`
+    /// and returns reference to it if found.
+    pub fn read_section_ref<K>(&self, key: K) -> Option<&Cfua>
+    where K: AsRef<str> {
+        self.find(key.as_ref()).and_then(CfuaType::as_section)
+    }
+
+    /// Searches for value of any type stored within `key`
+    /// and returns reference to it if found.
+    pub fn read_value<K>(&self, key: K) -> Option<&CfuaType>
+    where K: AsRef<str> {
+        self.find(key.as_ref())
+    }
+
+    /// Returns number of key-value pairs, including sections.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns `true` if structure holds no values.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns an iterator over key-value pairs, in order of their
+    /// appearance, without copying them.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter { inner: self.data.iter() }
+    }
+
     /// Returns a copy of all data stored in key-value pairs.
+    /// To avoid copying, use [`iter`] instead.
+    /// 
+    /// [`iter`]: Self::iter
     pub fn get_all(&self) -> Vec<(String, CfuaType)> {
         self.data.clone()
     }
 }
 
+/// Returns [`CfuaError::NestedSection`] if `value` is a section
+/// which has sections itself, as these cannot be written.
+pub(crate) fn check_nesting(value: &CfuaType) -> Result<(), CfuaError> {
+    if let CfuaType::Section(section) = value
+        && let Some(name) = section.sections().into_iter().next() {
+        return Err(CfuaError::NestedSection(name));
+    }
+    Ok(())
+}
+
+/// Iterator over key-value pairs of [`Cfua`], created with [`Cfua::iter`].
+#[derive(Debug, Clone)]
+pub struct Iter<'a> {
+    inner: slice::Iter<'a, (String, CfuaType)>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a str, &'a CfuaType);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(key, value)| (key.as_str(), value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(key, value)| (key.as_str(), value))
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+impl FusedIterator for Iter<'_> {}
+
+impl<'a> IntoIterator for &'a Cfua {
+    type Item = (&'a str, &'a CfuaType);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Returns error if `key` couldn't be read back by parser.
+pub(crate) fn check_key(key: &str) -> Result<(), CfuaError> {
+    if is_valid_name(key) {
+        Ok(())
+    } else {
+        Err(CfuaError::InvalidKey(key.to_string()))
+    }
+}
+
+/// Returns error if array with `elements` couldn't be read back
+/// by parser: arrays must not be empty, and their elements must be
+/// values of the same type, other than arrays and sections.
+pub(crate) fn check_array(elements: &[CfuaType]) -> Result<(), CfuaError> {
+    let unwritable = |message: String| Err(CfuaError::Conversion { key: String::new(), message });
+    let Some(first) = elements.first() else {
+        return unwritable("empty array cannot be written".to_string());
+    };
+    for element in elements {
+        if let CfuaType::Array(_) | CfuaType::Section(_) = element {
+            return unwritable(format!("{} cannot be written as array element", element.type_name()));
+        }
+        if !element.has_same_type(first) {
+            return unwritable(format!("array mixing {} and {} values cannot be written", first.type_name(), element.type_name()));
+        }
+    }
+    Ok(())
+}
+
+fn type_mismatch(key: &str, expected: &'static str, found: &CfuaType) -> CfuaError {
+    CfuaError::TypeMismatch { key: key.to_string(), expected, found: found.type_name() }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -183,6 +804,120 @@ mod tests {
         map.push(("is-cfua".to_string(), CfuaType::Bool(true)));
         map.push(("purpose".to_string(), CfuaType::String("Testing builder functions".to_string())));
 
-        assert_eq!(data, Cfua { data: map });
+        let index = HashMap::from([("is-cfua".to_string(), 0), ("purpose".to_string(), 1)]);
+
+        assert_eq!(data.index, index);
+        assert_eq!(data, Cfua { data: map, index, comments: vec![None, None], radixes: vec![Radix::Decimal; 2] });
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn typed_reads() {
+        let mut data = Cfua::create();
+        data.write_string("port", "8080");
+        data.write_integer("workers", 4);
+
+        assert_eq!(data.get_integer("workers").unwrap(), 4);
+        assert!(matches!(
+            data.get_integer("port"),
+            Err(CfuaError::TypeMismatch { key, expected: "integer", found: "string" }) if key == "port"
+        ));
+        assert!(matches!(data.get_bool("debug"), Err(CfuaError::KeyNotFound(key)) if key == "debug"));
+        assert_eq!(
+            data.get_float("port").unwrap_err().to_string(),
+            "expected float for key 'port', found string"
+        );
+    }
+
+    #[test]
+    fn index_follows_changes() {
+        let mut data = Cfua::create();
+        data.write_integer("a", 1);
+        data.write_integer("b", 2);
+        data.write_integer("a", 3);
+
+        assert_eq!(data.read_integer("a"), Some(1));
+        data.remove_at(0);
+        assert_eq!(data.read_integer("a"), Some(3));
+        data.insert_before("b", "c", 4).unwrap();
+        data.rename("b", "d").unwrap();
+        assert_eq!(data.index, HashMap::from([("c".to_string(), 0), ("d".to_string(), 1), ("a".to_string(), 2)]));
+    }
+
+    #[test]
+    fn borrowing_reads() {
+        let data = Cfua::from_string("name: 'cfua\ncounts: [1, 2]\n@server\nport: 80\n").unwrap();
+
+        assert_eq!(data.read_str("name"), Some("cfua"));
+        assert_eq!(data.read_str("counts"), None);
+        assert_eq!(data.read_array_ref("counts"), Some(&[CfuaType::Integer(1), CfuaType::Integer(2)][..]));
+        assert_eq!(data.read_section_ref("server").and_then(|s| s.read_integer("port")), Some(80));
+
+        let keys: Vec<&str> = data.iter().map(|(key, _)| key).collect();
+        assert_eq!(keys, vec!["name", "counts", "server"]);
+        assert_eq!((&data).into_iter().len(), data.len());
+        for (key, value) in &data {
+            assert_eq!(data.read_value(key), Some(value));
+        }
+    }
+
+    #[test]
+    fn checked_writes() {
+        let mut data = Cfua::create();
+
+        assert!(matches!(data.try_write_integer("Port Number", 1), Err(CfuaError::InvalidKey(key)) if key == "Port Number"));
+        assert!(data.try_write_bool("-debug", true).is_err());
+        assert!(data.try_write("", 1).is_err());
+        // hyphen may follow only two or more letters, as in parser
+        assert!(data.try_write_integer("a-b", 1).is_err());
+        assert!(Cfua::from_string("a-b: 1\n").is_err());
+        data.try_write_integer("ab-c", 1).unwrap();
+        data.try_write_string("name-", "trailing hyphen").unwrap();
+        // names may contain any lowercase letter, including `z`
+        data.try_write_integer("zone", 1).unwrap();
+
+        let mut section = Cfua::create();
+        section.write_integer("Bad", 1);
+        assert!(section.validate().is_err());
+        assert!(data.try_write_section("server", section.clone()).is_err());
+        assert!(data.try_write("server", section).is_err());
+        assert!(data.try_write_section("Server", Cfua::create()).is_err());
+
+        assert_eq!(data.len(), 3);
+        assert!(data.validate().is_ok());
+        assert_eq!(Cfua::from_string(data.to_string()).unwrap(), data);
+    }
+
+    #[test]
+    fn unwritable_values() {
+        let mut section = Cfua::create();
+        section.write_value("ports", CfuaType::Array(Vec::new()));
+        let mut structure = Cfua::create();
+        structure.write_section("server", section).unwrap();
+        assert_eq!(structure.validate().unwrap_err().to_string(), "empty array cannot be written (at 'server.ports')");
+
+        let nested = CfuaType::Array(vec![CfuaType::Array(vec![CfuaType::Integer(1)])]);
+        let mixed = CfuaType::Array(vec![CfuaType::Integer(1), CfuaType::Float(2.5)]);
+        for (value, message) in [
+            (nested, "array cannot be written as array element (at 'value')"),
+            (mixed, "array mixing integer and float values cannot be written (at 'value')"),
+        ] {
+            let mut structure = Cfua::create();
+            assert_eq!(structure.try_write("value", value.clone()).unwrap_err().to_string(), message);
+            structure.write_value("value", value);
+            assert_eq!(structure.validate().unwrap_err().to_string(), message);
+        }
+    }
+
+    #[test]
+    fn nested_sections() {
+        let mut inner = Cfua::create();
+        inner.write_integer("a", 1);
+        let mut middle = Cfua::create();
+        middle.write_section("inner", inner).unwrap();
+
+        let mut structure = Cfua::create();
+        assert!(matches!(structure.try_write_section("mid", middle.clone()), Err(CfuaError::NestedSection(name)) if name == "inner"));
+        structure.write_value("mid", CfuaType::Section(middle));
+        assert!(matches!(structure.validate(), Err(CfuaError::NestedSection(name)) if name == "inner"));
+    }
+}
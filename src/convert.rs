@@ -0,0 +1,602 @@
+//! Traits for converting Rust values from and into cfua values.
+//!
+//! Types implementing these traits can be used with generic [`Cfua::write`],
+//! [`Cfua::read`] and [`Cfua::get`] functions.
+//!
+//! Conversions are implemented for:
+//! - all integer widths, with range checking: 128-bit integers out of `i64`
+//!   and `u64` range can be written only with `bigint` feature (which also
+//!   adds `num_bigint::BigInt` in both directions), and integers which don't
+//!   fit in target type cannot be read ([`CfuaError::OutOfRange`]),
+//! - `f32` and `f64` (floats may also be read from integer values, which
+//!   they can represent exactly),
+//! - `String`, `&str`, `bool`, `PathBuf` and `&Path`,
+//! - `Vec<T>` and `[T; N]`, where `T` is one of above (or `Option` of it),
+//!   as marked by [`ArrayElement`] trait, so that arrays are never nested
+//!   and never mix value types; empty arrays, and arrays with elements
+//!   which are `None`, cannot be written ([`CfuaError::Conversion`]),
+//! - `Option<T>`, which is not written when `None`, and is read as `None`
+//!   when key is missing,
+//! - [`Cfua`] (as a section) and [`CfuaType`] itself.
+//!
+//! ## Example
+//!
+//! ```
+//! use cfua::Cfua;
+//!
+//! let mut data = Cfua::create();
+//! data.write("port", 8080u16)?;
+//! data.write("hosts", vec!["alpha", "beta"])?;
+//! data.write("timeout", None::<f64>)?;
+//! assert!(data.write("tags", Vec::<String>::new()).is_err());
+//!
+//! assert_eq!(data.read::<u16>("port"), Some(8080));
+//! assert_eq!(data.read::<u8>("port"), None);
+//! assert_eq!(data.get::<Vec<String>>("hosts").unwrap(), vec!["alpha", "beta"]);
+//! assert_eq!(data.get::<Option<f64>>("timeout").unwrap(), None);
+//! # Ok::<(), cfua::CfuaError>(())
+//! ```
+//!
+//! [`Cfua::write`]: crate::Cfua::write
+//! [`Cfua::read`]: crate::Cfua::read
+//! [`Cfua::get`]: crate::Cfua::get
+
+use std::{any::type_name, path::{Path, PathBuf}};
+
+use crate::{cfua::check_key, Cfua, CfuaError, CfuaType};
+
+/// Conversion of Rust value into cfua value.
+///
+/// Keys in returned errors are relative to converted value, e.g. `[2]`
+/// for third element of an array, or empty.
+pub trait IntoCfua {
+    /// Converts `self` into cfua value. Returns `Ok(None)` if value
+    /// should not be written at all, which is the case for `Option::None`
+    /// only, or an error if value cannot be represented in cfua.
+    fn into_cfua(self) -> Result<Option<CfuaType>, CfuaError>;
+}
+
+/// Marker of types which are converted into array elements: scalar values
+/// of a single cfua type. Only vectors and arrays of these types can be
+/// converted into cfua arrays, as parser rejects nested arrays and arrays
+/// mixing value types.
+///
+/// ```compile_fail
+/// let mut data = cfua::Cfua::create();
+/// data.write("nested", vec![vec![1]]);
+/// ```
+pub trait ArrayElement: IntoCfua {}
+
+/// Conversion of cfua value into Rust value.
+///
+/// Keys in returned errors are relative to converted value, e.g. `[2]`
+/// for third element of an array, or empty.
+pub trait FromCfua: Sized {
+    /// Converts cfua `value` into `Self`.
+    fn from_cfua(value: &CfuaType) -> Result<Self, CfuaError>;
+
+    /// Returns value used when key is missing.
+    /// By default it is [`CfuaError::KeyNotFound`] error.
+    fn from_missing() -> Result<Self, CfuaError> {
+        Err(CfuaError::KeyNotFound(String::new()))
+    }
+}
+
+fn mismatch(expected: &'static str, found: &CfuaType) -> CfuaError {
+    CfuaError::TypeMismatch { key: String::new(), expected, found: found.type_name() }
+}
+
+fn out_of_range<T>(value: impl ToString) -> CfuaError {
+    CfuaError::OutOfRange { key: String::new(), value: value.to_string(), target: type_name::<T>() }
+}
+
+fn conversion(message: &str) -> CfuaError {
+    CfuaError::Conversion { key: String::new(), message: message.to_string() }
+}
+
+impl IntoCfua for CfuaType {
+    fn into_cfua(self) -> Result<Option<CfuaType>, CfuaError> {
+        Ok(Some(self))
+    }
+}
+
+impl FromCfua for CfuaType {
+    fn from_cfua(value: &CfuaType) -> Result<Self, CfuaError> {
+        Ok(value.clone())
+    }
+}
+
+macro_rules! into_integer {
+    ($($ty: ty),*) => {
+        $(
+            impl IntoCfua for $ty {
+                fn into_cfua(self) -> Result<Option<CfuaType>, CfuaError> {
+                    Ok(Some(CfuaType::Integer(self.into())))
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! from_integer {
+    ($($ty: ty),*) => {
+        $(
+            impl FromCfua for $ty {
+                fn from_cfua(value: &CfuaType) -> Result<Self, CfuaError> {
+                    match value {
+                        CfuaType::Integer(i) => <$ty>::try_from(*i).map_err(|_| out_of_range::<$ty>(i)),
+                        CfuaType::Unsigned(u) => <$ty>::try_from(*u).map_err(|_| out_of_range::<$ty>(u)),
+                        #[cfg(feature = "bigint")]
+                        CfuaType::BigInt(i) => <$ty>::try_from(i).map_err(|_| out_of_range::<$ty>(i)),
+                        other => Err(mismatch("integer", other)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+into_integer!(i8, i16, i32, i64, u8, u16, u32);
+from_integer!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+impl IntoCfua for isize {
+    fn into_cfua(self) -> Result<Option<CfuaType>, CfuaError> {
+        // `isize` is at most 64 bits wide on supported platforms
+        Ok(Some(CfuaType::Integer(self as i64)))
+    }
+}
+
+impl IntoCfua for u64 {
+    /// Converts integer into [`CfuaType::Integer`],
+    /// or [`CfuaType::Unsigned`] if it doesn't fit in `i64`.
+    fn into_cfua(self) -> Result<Option<CfuaType>, CfuaError> {
+        match i64::try_from(self) {
+            Ok(i) => Ok(Some(CfuaType::Integer(i))),
+            Err(_) => Ok(Some(CfuaType::Unsigned(self))),
+        }
+    }
+}
+
+impl IntoCfua for usize {
+    fn into_cfua(self) -> Result<Option<CfuaType>, CfuaError> {
+        // `usize` is at most 64 bits wide on supported platforms
+        (self as u64).into_cfua()
+    }
+}
+
+#[cfg(feature = "bigint")]
+impl IntoCfua for num_bigint::BigInt {
+    /// Converts integer into the smallest fitting representation.
+    fn into_cfua(self) -> Result<Option<CfuaType>, CfuaError> {
+        if let Ok(i) = i64::try_from(&self) {
+            Ok(Some(CfuaType::Integer(i)))
+        } else if let Ok(u) = u64::try_from(&self) {
+            Ok(Some(CfuaType::Unsigned(u)))
+        } else {
+            Ok(Some(CfuaType::BigInt(self)))
+        }
+    }
+}
+
+#[cfg(feature = "bigint")]
+impl FromCfua for num_bigint::BigInt {
+    fn from_cfua(value: &CfuaType) -> Result<Self, CfuaError> {
+        match value {
+            CfuaType::Integer(i) => Ok((*i).into()),
+            CfuaType::Unsigned(u) => Ok((*u).into()),
+            CfuaType::BigInt(i) => Ok(i.clone()),
+            other => Err(mismatch("integer", other)),
+        }
+    }
+}
+
+macro_rules! into_wide_integer {
+    ($($ty: ty),*) => {
+        $(
+            impl IntoCfua for $ty {
+                /// Converts integer into the smallest fitting representation.
+                /// Without `bigint` feature, returns [`CfuaError::OutOfRange`]
+                /// for integers out of `i64` and `u64` range.
+                fn into_cfua(self) -> Result<Option<CfuaType>, CfuaError> {
+                    if let Ok(i) = i64::try_from(self) {
+                        Ok(Some(CfuaType::Integer(i)))
+                    } else if let Ok(u) = u64::try_from(self) {
+                        Ok(Some(CfuaType::Unsigned(u)))
+                    } else {
+                        big_integer(self)
+                    }
+                }
+            }
+        )*
+    };
+}
+
+into_wide_integer!(i128, u128);
+
+#[cfg(feature = "bigint")]
+fn big_integer(value: impl Into<num_bigint::BigInt>) -> Result<Option<CfuaType>, CfuaError> {
+    Ok(Some(CfuaType::BigInt(value.into())))
+}
+
+#[cfg(not(feature = "bigint"))]
+fn big_integer(value: impl ToString) -> Result<Option<CfuaType>, CfuaError> {
+    Err(CfuaError::OutOfRange { key: String::new(), value: value.to_string(), target: "i64 or u64" })
+}
+
+/// Returns number of significant bits of integer `magnitude` (from its
+/// highest to its lowest set bit). Integers with more significant bits
+/// than float's mantissa cannot be converted into a float exactly.
+fn significant_bits(magnitude: u64) -> u32 {
+    if magnitude == 0 {
+        0
+    } else {
+        u64::BITS - magnitude.leading_zeros() - magnitude.trailing_zeros()
+    }
+}
+
+/// Returns an error if `value` is an integer which float `T`
+/// with `mantissa` bits cannot represent exactly.
+fn check_exact<T>(value: &CfuaType, mantissa: u32) -> Result<(), CfuaError> {
+    match value {
+        CfuaType::Integer(i) if significant_bits(i.unsigned_abs()) > mantissa => Err(out_of_range::<T>(i)),
+        CfuaType::Unsigned(u) if significant_bits(*u) > mantissa => Err(out_of_range::<T>(u)),
+        #[cfg(feature = "bigint")]
+        CfuaType::BigInt(i) if i.magnitude().bits() - i.magnitude().trailing_zeros().unwrap_or(0) > mantissa.into() => {
+            Err(out_of_range::<T>(i))
+        },
+        _ => Ok(()),
+    }
+}
+
+impl IntoCfua for f64 {
+    fn into_cfua(self) -> Result<Option<CfuaType>, CfuaError> {
+        Ok(Some(CfuaType::Float(self)))
+    }
+}
+
+impl IntoCfua for f32 {
+    fn into_cfua(self) -> Result<Option<CfuaType>, CfuaError> {
+        Ok(Some(CfuaType::Float(self.into())))
+    }
+}
+
+impl FromCfua for f64 {
+    /// Converts float, or integer which can be represented exactly.
+    fn from_cfua(value: &CfuaType) -> Result<Self, CfuaError> {
+        check_exact::<f64>(value, f64::MANTISSA_DIGITS)?;
+        match value {
+            CfuaType::Float(f) => Ok(*f),
+            CfuaType::Integer(i) => Ok(*i as f64),
+            CfuaType::Unsigned(u) => Ok(*u as f64),
+            #[cfg(feature = "bigint")]
+            CfuaType::BigInt(i) => i.to_string().parse().ok()
+                .filter(|f: &f64| f.is_finite())
+                .ok_or_else(|| out_of_range::<f64>(i)),
+            other => Err(mismatch("float", other)),
+        }
+    }
+}
+
+impl FromCfua for f32 {
+    /// Converts float, or integer which can be represented exactly.
+    /// Floats are rounded to the nearest `f32`.
+    fn from_cfua(value: &CfuaType) -> Result<Self, CfuaError> {
+        check_exact::<f32>(value, f32::MANTISSA_DIGITS)?;
+        let f = f64::from_cfua(value)?;
+        if f.is_finite() && f.abs() > f32::MAX.into() {
+            Err(out_of_range::<f32>(f))
+        } else {
+            Ok(f as f32)
+        }
+    }
+}
+
+impl IntoCfua for bool {
+    fn into_cfua(self) -> Result<Option<CfuaType>, CfuaError> {
+        Ok(Some(CfuaType::Bool(self)))
+    }
+}
+
+impl FromCfua for bool {
+    fn from_cfua(value: &CfuaType) -> Result<Self, CfuaError> {
+        match value {
+            CfuaType::Bool(b) => Ok(*b),
+            other => Err(mismatch("boolean", other)),
+        }
+    }
+}
+
+impl IntoCfua for String {
+    fn into_cfua(self) -> Result<Option<CfuaType>, CfuaError> {
+        Ok(Some(CfuaType::String(self)))
+    }
+}
+
+impl IntoCfua for &str {
+    fn into_cfua(self) -> Result<Option<CfuaType>, CfuaError> {
+        Ok(Some(CfuaType::String(self.to_string())))
+    }
+}
+
+impl FromCfua for String {
+    fn from_cfua(value: &CfuaType) -> Result<Self, CfuaError> {
+        match value {
+            CfuaType::String(s) => Ok(s.clone()),
+            other => Err(mismatch("string", other)),
+        }
+    }
+}
+
+impl IntoCfua for PathBuf {
+    fn into_cfua(self) -> Result<Option<CfuaType>, CfuaError> {
+        self.as_path().into_cfua()
+    }
+}
+
+impl IntoCfua for &Path {
+    /// Converts path into string. Note that paths which are not valid
+    /// UTF-8 are converted lossily.
+    fn into_cfua(self) -> Result<Option<CfuaType>, CfuaError> {
+        Ok(Some(CfuaType::String(self.to_string_lossy().into_owned())))
+    }
+}
+
+impl FromCfua for PathBuf {
+    fn from_cfua(value: &CfuaType) -> Result<Self, CfuaError> {
+        String::from_cfua(value).map(PathBuf::from)
+    }
+}
+
+macro_rules! array_element {
+    ($($ty: ty),*) => {
+        $(
+            impl ArrayElement for $ty {}
+        )*
+    };
+}
+
+array_element!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64, bool, String, &str, PathBuf, &Path);
+#[cfg(feature = "bigint")]
+array_element!(num_bigint::BigInt);
+
+impl<T> ArrayElement for Option<T>
+where T: ArrayElement {}
+
+impl<T> IntoCfua for Vec<T>
+where T: ArrayElement {
+    /// Converts elements into an array. Returns [`CfuaError::Conversion`]
+    /// if vector is empty or any of its elements is `None`, as cfua arrays
+    /// cannot be empty or hold empty values.
+    fn into_cfua(self) -> Result<Option<CfuaType>, CfuaError> {
+        if self.is_empty() {
+            return Err(conversion("empty array cannot be written"));
+        }
+        let elements = self.into_iter()
+            .enumerate()
+            .map(|(i, element)| match element.into_cfua() {
+                Ok(Some(element)) => Ok(element),
+                Ok(None) => Err(conversion("array cannot hold empty values")),
+                Err(e) => Err(e),
+            }.map_err(|e| e.at_key(&format!("[{i}]"))))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Some(CfuaType::Array(elements)))
+    }
+}
+
+impl<T, const N: usize> IntoCfua for [T; N]
+where T: ArrayElement {
+    fn into_cfua(self) -> Result<Option<CfuaType>, CfuaError> {
+        Vec::from(self).into_cfua()
+    }
+}
+
+impl<T> FromCfua for Vec<T>
+where T: FromCfua {
+    fn from_cfua(value: &CfuaType) -> Result<Self, CfuaError> {
+        match value {
+            CfuaType::Array(v) => v.iter()
+                .enumerate()
+                .map(|(i, el)| T::from_cfua(el).map_err(|e| e.at_key(&format!("[{i}]"))))
+                .collect(),
+            other => Err(mismatch("array", other)),
+        }
+    }
+}
+
+impl<T, const N: usize> FromCfua for [T; N]
+where T: FromCfua {
+    fn from_cfua(value: &CfuaType) -> Result<Self, CfuaError> {
+        Vec::from_cfua(value)?
+            .try_into()
+            .map_err(|v: Vec<T>| out_of_range::<Self>(format!("array of {} elements", v.len())))
+    }
+}
+
+impl<T> IntoCfua for Option<T>
+where T: IntoCfua {
+    fn into_cfua(self) -> Result<Option<CfuaType>, CfuaError> {
+        match self {
+            Some(value) => value.into_cfua(),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<T> FromCfua for Option<T>
+where T: FromCfua {
+    fn from_cfua(value: &CfuaType) -> Result<Self, CfuaError> {
+        T::from_cfua(value).map(Some)
+    }
+
+    fn from_missing() -> Result<Self, CfuaError> {
+        Ok(None)
+    }
+}
+
+impl IntoCfua for Cfua {
+    fn into_cfua(self) -> Result<Option<CfuaType>, CfuaError> {
+        Ok(Some(CfuaType::Section(self)))
+    }
+}
+
+impl FromCfua for Cfua {
+    fn from_cfua(value: &CfuaType) -> Result<Self, CfuaError> {
+        match value {
+            CfuaType::Section(s) => Ok(s.clone()),
+            other => Err(mismatch("section", other)),
+        }
+    }
+}
+
+impl Cfua {
+    /// Appends `value` of any supported type with `key` into the end
+    /// of structure. Nothing is written if `value` is `None`. Returns
+    /// an error, writing nothing, if `value` cannot be converted,
+    /// e.g. it's an empty vector or an integer out of `i64` range,
+    /// or if it's a section holding other sections.
+    pub fn write<V>(&mut self, key: impl ToString, value: V) -> Result<(), CfuaError>
+    where V: IntoCfua {
+        let key = key.to_string();
+        match value.into_cfua().map_err(|e| e.at_key(&key))? {
+            Some(CfuaType::Section(section)) => self.write_section(key, section),
+            Some(value) => {
+                self.write_value(key, value);
+                Ok(())
+            },
+            None => Ok(()),
+        }
+    }
+
+    /// Like [`write`](Self::write), but returns [`CfuaError::InvalidKey`]
+    /// instead of writing a `key` (or a key inside of section `value`)
+    /// which couldn't be read back.
+    pub fn try_write<V>(&mut self, key: impl ToString, value: V) -> Result<(), CfuaError>
+    where V: IntoCfua {
+        let key = key.to_string();
+        match value.into_cfua().map_err(|e| e.at_key(&key))? {
+            Some(CfuaType::Section(section)) => self.try_write_section(key, section),
+            Some(value) => self.try_write_value(key, value),
+            None => check_key(&key),
+        }
+    }
+
+    /// Searches for value stored within `key` and converts it to `T`.
+    /// Returns `None` if value is missing or cannot be converted.
+    pub fn read<T>(&self, key: impl AsRef<str>) -> Option<T>
+    where T: FromCfua {
+        self.get(key).ok()
+    }
+
+    /// Searches for value stored within `key` and converts it to `T`.
+    /// Returns an error telling whether key is missing, holds a value
+    /// of different type or a value out of range of `T`.
+    pub fn get<T>(&self, key: impl AsRef<str>) -> Result<T, CfuaError>
+    where T: FromCfua {
+        let key = key.as_ref();
+        match self.find(key) {
+            Some(value) => T::from_cfua(value),
+            None => T::from_missing(),
+        }.map_err(|e| e.at_key(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integer_ranges() {
+        let mut data = Cfua::create();
+        data.write("small", 200u8).unwrap();
+        data.write("negative", -1i32).unwrap();
+
+        assert_eq!(data.read::<u8>("small"), Some(200));
+        assert_eq!(data.read::<i8>("small"), None);
+        assert_eq!(data.read::<u64>("negative"), None);
+        assert_eq!(data.read::<i128>("negative"), Some(-1));
+        assert!(matches!(
+            data.get::<i8>("small"),
+            Err(CfuaError::OutOfRange { key, value, target: "i8" }) if key == "small" && value == "200"
+        ));
+    }
+
+    #[test]
+    fn wide_integers() {
+        let mut data = Cfua::create();
+        data.write("small", -5i128).unwrap();
+        data.write("unsigned", u128::from(u64::MAX)).unwrap();
+
+        assert_eq!(data.read::<CfuaType>("small"), Some(CfuaType::Integer(-5)));
+        assert_eq!(data.read::<i128>("small"), Some(-5));
+        assert_eq!(data.read::<CfuaType>("unsigned"), Some(CfuaType::Unsigned(u64::MAX)));
+        assert_eq!(data.read::<u128>("unsigned"), Some(u64::MAX.into()));
+        #[cfg(not(feature = "bigint"))]
+        assert!(matches!(
+            data.write("huge", u128::MAX),
+            Err(CfuaError::OutOfRange { key, target: "i64 or u64", .. }) if key == "huge"
+        ));
+        #[cfg(not(feature = "bigint"))]
+        assert_eq!(data.read::<CfuaType>("huge"), None);
+        #[cfg(feature = "bigint")]
+        data.write("huge", u128::MAX).unwrap();
+        #[cfg(feature = "bigint")]
+        assert_eq!(data.read::<u128>("huge"), Some(u128::MAX));
+    }
+
+    #[test]
+    fn integers_as_floats() {
+        let mut data = Cfua::create();
+        data.write("exact", 1i64 << 53).unwrap();
+        data.write("inexact", (1i64 << 53) + 1).unwrap();
+        data.write("large", u64::MAX - 2047).unwrap();
+        data.write("float-limit", 16777217).unwrap();
+
+        assert_eq!(data.read::<f64>("exact"), Some(9007199254740992.0));
+        assert!(matches!(
+            data.get::<f64>("inexact"),
+            Err(CfuaError::OutOfRange { key, target: "f64", .. }) if key == "inexact"
+        ));
+        assert_eq!(data.read::<f64>("large"), Some((u64::MAX - 2047) as f64));
+        assert_eq!(data.read::<f64>("float-limit"), Some(16777217.0));
+        assert_eq!(data.read::<f32>("float-limit"), None);
+    }
+
+    #[test]
+    fn unwritable_arrays() {
+        let mut data = Cfua::create();
+        let empty = data.write("empty", Vec::<i64>::new()).unwrap_err();
+        assert_eq!(empty.to_string(), "empty array cannot be written (at 'empty')");
+        let none = data.write("none", vec![Some(1), None, Some(3)]).unwrap_err();
+        assert!(matches!(none, CfuaError::Conversion { key, .. } if key == "none[1]"));
+        data.write("complete", [Some(1), Some(3)]).unwrap();
+
+        assert_eq!(data.read::<CfuaType>("empty"), None);
+        assert_eq!(data.read::<CfuaType>("none"), None);
+        assert_eq!(data.get::<Vec<i64>>("complete").unwrap(), vec![1, 3]);
+
+        let mut outer = Cfua::create();
+        outer.write("inner", Cfua::create()).unwrap();
+        assert!(matches!(data.write("outer", outer), Err(CfuaError::NestedSection(_))));
+        assert!(data.read::<CfuaType>("outer").is_none());
+        assert!(data.validate().is_ok());
+    }
+
+    #[test]
+    fn compound_values() {
+        let mut data = Cfua::create();
+        data.write("path", PathBuf::from("/etc/cfua")).unwrap();
+        data.write("weights", [0.5f32, 0.25]).unwrap();
+        data.write("missing", None::<bool>).unwrap();
+        data.write_value("mixed", CfuaType::Array(vec![CfuaType::Integer(1), CfuaType::String("two".to_string())]));
+
+        assert_eq!(data.get::<PathBuf>("path").unwrap(), PathBuf::from("/etc/cfua"));
+        assert_eq!(data.get::<[f32; 2]>("weights").unwrap(), [0.5, 0.25]);
+        assert_eq!(data.get::<Option<bool>>("missing").unwrap(), None);
+        assert!(data.get::<[f32; 3]>("weights").is_err());
+        assert!(matches!(data.get::<bool>("missing"), Err(CfuaError::KeyNotFound(key)) if key == "missing"));
+        assert!(matches!(
+            data.get::<Vec<i64>>("mixed"),
+            Err(CfuaError::TypeMismatch { key, .. }) if key == "mixed[1]"
+        ));
+    }
+}
@@ -0,0 +1,295 @@
+//! Deserializing Rust types from cfua data with serde.
+//!
+//! Top-level values and sections map to structs (or maps), arrays map
+//! to sequences like `Vec`, and remaining values map to corresponding
+//! primitive types. Unit enum variants are read from strings.
+//!
+//! Note that cfua keys consist of lowercase letters and hyphens, so
+//! structs usually need `#[serde(rename_all = "kebab-case")]`.
+//!
+//! ## Example
+//!
+//! ```
+//! use serde::Deserialize;
+//!
+//! #[derive(Deserialize)]
+//! #[serde(rename_all = "kebab-case")]
+//! struct Config {
+//!     name: String,
+//!     server: Server,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct Server {
+//!     port: u16,
+//! }
+//!
+//! let config: Config = cfua::from_str("name: 'example\n@server\nport: 8080\n").unwrap();
+//! assert_eq!(config.server.port, 8080);
+//! ```
+
+use std::{fmt::Display, iter::Enumerate, slice};
+
+use serde::de::{self, value::BorrowedStrDeserializer, DeserializeOwned, DeserializeSeed, IntoDeserializer, Visitor};
+
+use crate::{cfua::CfuaType, Cfua, CfuaError};
+
+impl de::Error for CfuaError {
+    fn custom<T>(msg: T) -> Self
+    where T: Display {
+        CfuaError::Serde { key: String::new(), message: msg.to_string() }
+    }
+}
+
+/// Deserializes `T` from string containing cfua data.
+pub fn from_str<T>(string: &str) -> Result<T, CfuaError>
+where T: DeserializeOwned {
+    let data = Cfua::from_string(string)?;
+    from_cfua(&data)
+}
+
+/// Deserializes `T` from already parsed cfua data.
+pub fn from_cfua<'de, T>(data: &'de Cfua) -> Result<T, CfuaError>
+where T: de::Deserialize<'de> {
+    T::deserialize(Deserializer::new(data))
+}
+
+/// Deserializer reading from top-level of [`Cfua`] structure.
+pub struct Deserializer<'de> {
+    data: &'de Cfua,
+}
+
+impl<'de> Deserializer<'de> {
+    /// Creates deserializer reading from `data`.
+    pub fn new(data: &'de Cfua) -> Self {
+        Self { data }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer<'de> {
+    type Error = CfuaError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where V: Visitor<'de> {
+        visitor.visit_map(MapAccess::new(self.data))
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where V: Visitor<'de> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value, Self::Error>
+    where V: Visitor<'de> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        enum identifier ignored_any
+    }
+}
+
+/// Deserializer reading single value.
+struct ValueDeserializer<'de>(&'de CfuaType);
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = CfuaError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where V: Visitor<'de> {
+        match self.0 {
+            CfuaType::Integer(i) => visitor.visit_i64(*i),
+            CfuaType::Unsigned(u) => visitor.visit_u64(*u),
+            #[cfg(feature = "bigint")]
+            CfuaType::BigInt(i) => if let Ok(i) = i128::try_from(i) {
+                visitor.visit_i128(i)
+            } else if let Ok(u) = u128::try_from(i) {
+                visitor.visit_u128(u)
+            } else {
+                Err(de::Error::custom(format!("integer {i} is out of 128-bit range")))
+            },
+            CfuaType::Float(f) => visitor.visit_f64(*f),
+            CfuaType::String(s) => visitor.visit_borrowed_str(s),
+            CfuaType::Bool(b) => visitor.visit_bool(*b),
+            CfuaType::Array(v) => visitor.visit_seq(SeqAccess { elements: v.iter().enumerate() }),
+            CfuaType::Section(s) => visitor.visit_map(MapAccess::new(s)),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where V: Visitor<'de> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value, Self::Error>
+    where V: Visitor<'de> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where V: Visitor<'de> {
+        match self.0 {
+            CfuaType::String(s) => visitor.visit_enum(s.as_str().into_deserializer()),
+            _ => Err(de::Error::custom("expected unit variant name as a string")),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+/// Gives access to key-value pairs of top-level data or a section.
+struct MapAccess<'de> {
+    entries: slice::Iter<'de, (String, CfuaType)>,
+    current: Option<&'de (String, CfuaType)>,
+}
+
+impl<'de> MapAccess<'de> {
+    fn new(data: &'de Cfua) -> Self {
+        Self {
+            entries: data.entries().iter(),
+            current: None,
+        }
+    }
+}
+
+impl<'de> de::MapAccess<'de> for MapAccess<'de> {
+    type Error = CfuaError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where K: DeserializeSeed<'de> {
+        self.current = self.entries.next();
+        match self.current {
+            Some((key, _)) => seed.deserialize(BorrowedStrDeserializer::new(key)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where V: DeserializeSeed<'de> {
+        let (key, value) = self.current.take()
+            .ok_or_else(|| <CfuaError as de::Error>::custom("value requested before key"))?;
+        seed.deserialize(ValueDeserializer(value)).map_err(|e| e.at_key(key))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+/// Gives access to array elements.
+struct SeqAccess<'de> {
+    elements: Enumerate<slice::Iter<'de, CfuaType>>,
+}
+
+impl<'de> de::SeqAccess<'de> for SeqAccess<'de> {
+    type Error = CfuaError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where T: DeserializeSeed<'de> {
+        match self.elements.next() {
+            Some((i, value)) => seed.deserialize(ValueDeserializer(value))
+                .map(Some)
+                .map_err(|e| e.at_key(&format!("[{i}]"))),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.elements.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    enum Mode {
+        Debug,
+        Release,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    struct Server {
+        port: u16,
+        hosts: Vec<String>,
+        max_connections: Option<u32>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    struct Config {
+        name: String,
+        ratio: f64,
+        enabled: bool,
+        mode: Mode,
+        server: Server,
+    }
+
+    #[test]
+    fn deserialize_struct() {
+        let example =
+r"name: 'example
+ratio: 1
+enabled: true
+mode: 'release
+@server
+port: 8080
+hosts: [
+#'alpha
+#'beta
+]
+";
+        let config: Config = from_str(example).unwrap();
+
+        assert_eq!(config, Config {
+            name: "example".to_string(),
+            ratio: 1.0,
+            enabled: true,
+            mode: Mode::Release,
+            server: Server {
+                port: 8080,
+                hosts: vec!["alpha".to_string(), "beta".to_string()],
+                max_connections: None,
+            },
+        });
+    }
+
+    #[test]
+    fn error_names_key() {
+        let example =
+r"name: 'example
+ratio: 0.5
+enabled: true
+mode: 'debug
+@server
+port: 70000
+hosts: [
+#'alpha
+]
+";
+        let error = from_str::<Config>(example).unwrap_err();
+        assert!(matches!(&error, CfuaError::Serde { key, .. } if key == "server.port"));
+
+        let error = from_str::<Vec<u8>>("").unwrap_err();
+        assert!(matches!(&error, CfuaError::Serde { key, .. } if key.is_empty()));
+
+        let error = from_str::<Server>("port: 1\nhosts: [1, 2]\n").unwrap_err();
+        assert!(matches!(&error, CfuaError::Serde { key, .. } if key == "hosts[0]"));
+    }
+}
@@ -0,0 +1,361 @@
+//! Lossless representation of cfua files, for editing them in place.
+//!
+//! [`Document`] keeps the source text split into [`Node`]s: entries,
+//! section declarations, comments and blank lines. Editing a value
+//! replaces only the text of its entry, so comments, blank lines, radix
+//! prefixes and array layout of the rest of the file are reprinted
+//! byte-for-byte.
+//!
+//! ## Example
+//!
+//! ```
+//! use cfua::Document;
+//!
+//! let input = "% server settings\n@server\nport: h1f90\n\nhosts: [\n#'alpha\n]\n";
+//! let mut document = Document::parse(input).unwrap();
+//! document.set("server.workers", 4).unwrap();
+//!
+//! assert_eq!(
+//!     document.to_string(),
+//!     "% server settings\n@server\nport: h1f90\n\nhosts: [\n#'alpha\n]\nworkers: 4\n"
+//! );
+//! assert_eq!(document.to_cfua().unwrap().query("server.port").unwrap().as_integer(), Some(8080));
+//! ```
+
+use std::fmt;
+
+use crate::{cfua::{check_key, CfuaType}, convert::IntoCfua, parser::ParserData, write::CfuaWriter, Cfua, CfuaError};
+
+/// Kind of a [`Node`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeKind {
+    /// Empty line.
+    Blank,
+    /// Comment line, starting with `%`.
+    Comment,
+    /// Section declaration, with section name.
+    Section(String),
+    /// Key with its value, which may span multiple lines.
+    Entry(String),
+}
+
+/// Part of a [`Document`], holding its source text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    kind: NodeKind,
+    text: String,
+}
+
+impl Node {
+    /// Returns kind of this node.
+    pub fn kind(&self) -> &NodeKind {
+        &self.kind
+    }
+
+    /// Returns source text of this node, including trailing newline
+    /// (unless it is the last line of input without one).
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+/// Cfua file which can be edited without losing its formatting.
+///
+/// Values are addressed with paths: `key` for top-level values,
+/// and `section.key` for values inside a section.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Document {
+    nodes: Vec<Node>,
+}
+
+/// Splits `path` into section name (if any) and key.
+fn split_path(path: &str) -> (Option<&str>, &str) {
+    match path.split_once('.') {
+        Some((section, key)) => (Some(section), key),
+        None => (None, path),
+    }
+}
+
+impl Document {
+    /// Parses `input`, keeping all of its text. Returns the same errors
+    /// as [`Cfua::from_string`].
+    pub fn parse(input: &str) -> Result<Self, CfuaError> {
+        let mut parser = ParserData::new();
+        let mut document = Document::default();
+
+        for line in input.split_inclusive('\n') {
+            // lines starting while parser is inside a value (e.g. array)
+            // are always continuation of previous entry
+            let starts_node = parser.is_reading();
+            parser.feed_line(line)?;
+            while parser.next_event().is_some() {}
+
+            let kind = match line.chars().next() {
+                _ if !starts_node => None,
+                Some('%') => Some(NodeKind::Comment),
+                Some('@') => Some(NodeKind::Section(line[1..].trim_end_matches(['\r', '\n']).to_string())),
+                Some('a'..='z') => {
+                    let end = line.find(':').unwrap_or(line.len());
+                    Some(NodeKind::Entry(line[..end].to_string()))
+                },
+                Some('\'') => None,
+                _ => Some(NodeKind::Blank),
+            };
+            match kind {
+                Some(kind) => document.nodes.push(Node { kind, text: line.to_string() }),
+                None => document.continue_entry(line),
+            }
+        }
+        parser.end_input()?;
+
+        Ok(document)
+    }
+
+    /// Appends `line` continuing the last entry. Nodes placed between
+    /// that entry and `line` (i.e. comments inside a multiline string)
+    /// become part of the entry.
+    fn continue_entry(&mut self, line: &str) {
+        let index = self.nodes.iter()
+            .rposition(|node| matches!(node.kind, NodeKind::Entry(_)));
+        match index {
+            Some(index) => {
+                let inner: String = self.nodes.drain(index + 1..).map(|node| node.text).collect();
+                let entry = &mut self.nodes[index].text;
+                entry.push_str(&inner);
+                entry.push_str(line);
+            },
+            None => self.nodes.push(Node { kind: NodeKind::Blank, text: line.to_string() }),
+        }
+    }
+
+    /// Returns all nodes, in order of appearance.
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    /// Parses document into [`Cfua`] structure.
+    pub fn to_cfua(&self) -> Result<Cfua, CfuaError> {
+        Cfua::from_string(self.to_string())
+    }
+
+    /// Returns index of entry with `key` in `section`.
+    fn find_entry(&self, section: Option<&str>, key: &str) -> Option<usize> {
+        let mut current = None;
+        for (index, node) in self.nodes.iter().enumerate() {
+            match &node.kind {
+                NodeKind::Section(name) => current = Some(name.as_str()),
+                NodeKind::Entry(entry) if current == section && entry == key => return Some(index),
+                _ => {},
+            }
+        }
+        None
+    }
+
+    /// Returns index at which a new entry of `section` should be inserted:
+    /// after its last entry, or right after its declaration. Missing
+    /// section is declared at the end of document. First top-level entry
+    /// is inserted before comments attached to the first section, but
+    /// after comments starting the document, which are its header.
+    fn insertion_point(&mut self, section: Option<&str>) -> usize {
+        let mut current = None;
+        let mut declared = section.is_none();
+        let mut last_entry = None;
+        let mut first_section = None;
+
+        for (index, node) in self.nodes.iter().enumerate() {
+            match &node.kind {
+                NodeKind::Section(name) => {
+                    current = Some(name.as_str());
+                    first_section.get_or_insert(index);
+                    if current == section && !declared {
+                        declared = true;
+                        last_entry = Some(index);
+                    }
+                },
+                NodeKind::Entry(_) if current == section => last_entry = Some(index),
+                _ => {},
+            }
+        }
+
+        match (last_entry, section) {
+            (Some(index), _) => index + 1,
+            // top-level entries go before comments preceding first section,
+            // unless these comments are header starting the document
+            (None, None) => {
+                let end = first_section.unwrap_or(self.nodes.len());
+                let mut index = end;
+                while index > 0 && self.nodes[index - 1].kind == NodeKind::Comment {
+                    index -= 1;
+                }
+                if index == 0 { end } else { index }
+            },
+            (None, Some(name)) => {
+                let index = self.nodes.len();
+                self.insert(index, Node { kind: NodeKind::Section(name.to_string()), text: format!("@{name}\n") });
+                index + 1
+            },
+        }
+    }
+
+    /// Inserts `node` at `index`, terminating previous line if needed.
+    fn insert(&mut self, index: usize, node: Node) {
+        if let Some(previous) = index.checked_sub(1).map(|i| &mut self.nodes[i].text)
+            && !previous.ends_with('\n') {
+            previous.push('\n');
+        }
+        self.nodes.insert(index, node);
+    }
+
+    /// Replaces value at `path`, keeping its position, or appends it
+    /// after the last value of its section (declaring the section if
+    /// needed). If `value` converts to `None`, existing value is removed
+    /// instead. Text of other entries is left untouched.
+    ///
+    /// Returns an error if `value` is a section, cannot be converted or
+    /// cannot be written (e.g. an empty array), or if key or section name
+    /// is not valid.
+    pub fn set<V>(&mut self, path: &str, value: V) -> Result<(), CfuaError>
+    where V: IntoCfua {
+        let (section, key) = split_path(path);
+        check_key(key)?;
+        if let Some(section) = section {
+            check_key(section)?;
+        }
+
+        let Some(value) = value.into_cfua()? else {
+            self.remove(path);
+            return Ok(());
+        };
+        if let CfuaType::Section(_) = value {
+            return Err(CfuaError::TypeMismatch { key: path.to_string(), expected: "value", found: "section" });
+        }
+
+        let mut text = CfuaWriter::default().format_entry(key, &value)
+            .map_err(|e| match section {
+                Some(section) => e.at_key(section),
+                None => e,
+            })?;
+
+        match self.find_entry(section, key) {
+            Some(index) => {
+                let node = &mut self.nodes[index];
+                if !node.text.ends_with('\n') {
+                    text.pop();
+                }
+                node.text = text;
+            },
+            None => {
+                let index = self.insertion_point(section);
+                self.insert(index, Node { kind: NodeKind::Entry(key.to_string()), text });
+            },
+        }
+        Ok(())
+    }
+
+    /// Removes entry at `path`. Returns `false` if there was no such entry.
+    pub fn remove(&mut self, path: &str) -> bool {
+        let (section, key) = split_path(path);
+        match self.find_entry(section, key) {
+            Some(index) => {
+                self.nodes.remove(index);
+                true
+            },
+            None => false,
+        }
+    }
+}
+
+impl fmt::Display for Document {
+    /// Prints document, reproducing parsed input exactly
+    /// except for edited entries.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for node in &self.nodes {
+            f.write_str(&node.text)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str =
+r"% example config
+name: 'multi
+% inside of a string
+'line
+mask: b1010
+
+@server
+port: h1f90
+hosts: [
+#'alpha
+#'beta
+]
+% last
+ratio: 0.5";
+
+    #[test]
+    fn lossless_reprint() {
+        let document = Document::parse(EXAMPLE).unwrap();
+        assert_eq!(document.to_string(), EXAMPLE);
+
+        let kinds: Vec<_> = document.nodes().iter().map(Node::kind).cloned().collect();
+        assert_eq!(kinds, vec![
+            NodeKind::Comment,
+            NodeKind::Entry("name".to_string()),
+            NodeKind::Entry("mask".to_string()),
+            NodeKind::Blank,
+            NodeKind::Section("server".to_string()),
+            NodeKind::Entry("port".to_string()),
+            NodeKind::Entry("hosts".to_string()),
+            NodeKind::Comment,
+            NodeKind::Entry("ratio".to_string()),
+        ]);
+        assert_eq!(document.to_cfua().unwrap(), Cfua::from_string(EXAMPLE).unwrap());
+    }
+
+    #[test]
+    fn edits_keep_formatting() {
+        let mut document = Document::parse(EXAMPLE).unwrap();
+        document.set("mask", 4).unwrap();
+        document.set("server.ratio", 0.25).unwrap();
+        document.set("debug", true).unwrap();
+        document.set("client.retries", 3).unwrap();
+        assert!(document.remove("server.hosts"));
+        assert!(!document.remove("hosts"));
+        assert!(document.set("server", Cfua::create()).is_err());
+
+        let expected =
+r"% example config
+name: 'multi
+% inside of a string
+'line
+mask: 4
+debug: true
+
+@server
+port: h1f90
+% last
+ratio: 0.25
+@client
+retries: 3
+";
+        assert_eq!(document.to_string(), expected);
+    }
+
+    #[test]
+    fn insert_into_empty_sections() {
+        let mut document = Document::parse("% header\n@server\n").unwrap();
+        document.set("name", "example").unwrap();
+        document.set("server.port", 80).unwrap();
+
+        assert_eq!(document.to_string(), "% header\nname: 'example\n@server\nport: 80\n");
+
+        let mut document = Document::parse("% header\n\n% server settings\n@server\n").unwrap();
+        document.set("name", "example").unwrap();
+
+        assert_eq!(document.to_string(), "% header\n\nname: 'example\n% server settings\n@server\n");
+    }
+}
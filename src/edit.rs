@@ -0,0 +1,278 @@
+use crate::{cfua::{check_key, check_nesting, CfuaType}, convert::IntoCfua, write::Radix, Cfua, CfuaError};
+
+impl Cfua {
+    /// Replaces value stored within `key`, keeping its position,
+    /// or appends it into the end of structure if there is none.
+    /// If `value` is `None`, existing value is removed instead. Returns
+    /// an error, leaving data unchanged, if `value` cannot be converted.
+    ///
+    /// If `key` is repeated (which parser allows), duplicates are
+    /// collapsed: value at the first position is replaced, and values
+    /// with the same key following it are removed.
+    ///
+    /// Replaced value is written in decimal, even if previous one
+    /// was read in other radix.
+    pub fn set<V>(&mut self, key: impl ToString, value: V) -> Result<(), CfuaError>
+    where V: IntoCfua {
+        let key = key.to_string();
+        match value.into_cfua().map_err(|e| e.at_key(&key))? {
+            Some(value) => {
+                check_nesting(&value).map_err(|e| e.at_key(&key))?;
+                self.replace(key, value);
+            },
+            None => {
+                self.remove(&key);
+            },
+        }
+        Ok(())
+    }
+
+    /// Like [`set`](Self::set), but returns [`CfuaError::InvalidKey`]
+    /// instead of writing a `key` which couldn't be read back.
+    pub fn try_set<V>(&mut self, key: impl ToString, value: V) -> Result<(), CfuaError>
+    where V: IntoCfua {
+        let key = key.to_string();
+        check_key(&key)?;
+        self.set(key, value)
+    }
+
+    /// Replaces value stored within `key`, or appends it into the end
+    /// of structure if there is none, without checking the key.
+    /// Values with the same key following the replaced one are removed,
+    /// and radix of replaced value is reset to decimal.
+    pub(crate) fn replace(&mut self, key: String, value: CfuaType) {
+        match self.position(&key) {
+            Some(index) => {
+                *self.value_at_mut(index) = value;
+                self.set_radix_at(index, Radix::Decimal);
+                while let Some(duplicate) = self.entries()[index + 1..].iter().position(|(k, _)| *k == key) {
+                    self.remove_at(index + 1 + duplicate);
+                }
+            },
+            None => self.write_value(key, value),
+        }
+    }
+
+    /// Removes value stored within `key` and returns it, or `None` if
+    /// there was no such value. If `key` is repeated, all values with it
+    /// are removed, and the first one is returned.
+    pub fn remove(&mut self, key: impl AsRef<str>) -> Option<CfuaType> {
+        let key = key.as_ref();
+        let index = self.position(key)?;
+        let value = self.remove_at(index).1;
+        while let Some(index) = self.position(key) {
+            self.remove_at(index);
+        }
+        Some(value)
+    }
+
+    /// Changes key of value stored within `from` to `to`, keeping its
+    /// position. Returns an error if there is no value with `from`,
+    /// if there is already a value with `to`, or if `to` is not a valid key.
+    pub fn rename(&mut self, from: impl ToString, to: impl ToString) -> Result<(), CfuaError> {
+        let (from, to) = (from.to_string(), to.to_string());
+        check_key(&to)?;
+        let index = self.position(&from).ok_or(CfuaError::KeyNotFound(from))?;
+        if self.position(&to).is_some() {
+            return Err(CfuaError::DuplicateKey(to));
+        }
+
+        self.rename_at(index, to);
+        Ok(())
+    }
+
+    /// Inserts `value` with `key` right before value stored within `anchor`.
+    /// Returns an error if there is no value with `anchor`, if there
+    /// is already a value with `key`, if `key` is not valid, or if `value`
+    /// is `None` or cannot be converted.
+    pub fn insert_before<V>(&mut self, anchor: impl ToString, key: impl ToString, value: V) -> Result<(), CfuaError>
+    where V: IntoCfua {
+        let anchor = anchor.to_string();
+        let index = self.position(&anchor).ok_or(CfuaError::KeyNotFound(anchor))?;
+        self.insert_checked(index, key.to_string(), value)
+    }
+
+    /// Inserts `value` with `key` right after value stored within `anchor`.
+    /// Returns an error if there is no value with `anchor`, if there
+    /// is already a value with `key`, if `key` is not valid, or if `value`
+    /// is `None` or cannot be converted.
+    pub fn insert_after<V>(&mut self, anchor: impl ToString, key: impl ToString, value: V) -> Result<(), CfuaError>
+    where V: IntoCfua {
+        let anchor = anchor.to_string();
+        let index = self.position(&anchor).ok_or(CfuaError::KeyNotFound(anchor))?;
+        self.insert_checked(index + 1, key.to_string(), value)
+    }
+
+    fn insert_checked<V>(&mut self, index: usize, key: String, value: V) -> Result<(), CfuaError>
+    where V: IntoCfua {
+        check_key(&key)?;
+        if self.position(&key).is_some() {
+            return Err(CfuaError::DuplicateKey(key));
+        }
+        let value = match value.into_cfua().map_err(|e| e.at_key(&key))? {
+            Some(value) => value,
+            None => return Err(CfuaError::Conversion { key, message: "no value to insert".to_string() }),
+        };
+        check_nesting(&value).map_err(|e| e.at_key(&key))?;
+        self.insert_at(index, key, value);
+        Ok(())
+    }
+
+    /// Gets value stored within `key` for in-place manipulation.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use cfua::{Cfua, CfuaType};
+    ///
+    /// let mut data = Cfua::create();
+    /// data.entry("retries").or_insert(CfuaType::Integer(3));
+    /// data.entry("retries").and_modify(|v| if let CfuaType::Integer(i) = v { *i += 1 });
+    ///
+    /// assert_eq!(data.read_integer("retries"), Some(4));
+    /// ```
+    pub fn entry(&mut self, key: impl ToString) -> Entry<'_> {
+        let key = key.to_string();
+        let index = self.position(&key);
+        Entry { data: self, key, index }
+    }
+}
+
+/// View into a single value of [`Cfua`], which may be missing.
+/// Created with [`Cfua::entry`].
+pub struct Entry<'a> {
+    data: &'a mut Cfua,
+    key: String,
+    index: Option<usize>,
+}
+
+impl<'a> Entry<'a> {
+    /// Returns key of this entry.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Returns `true` if value with entry's key exists.
+    pub fn exists(&self) -> bool {
+        self.index.is_some()
+    }
+
+    /// Modifies existing value with `f`. Does nothing if value is missing.
+    pub fn and_modify<F>(self, f: F) -> Self
+    where F: FnOnce(&mut CfuaType) {
+        if let Some(index) = self.index {
+            f(self.data.value_at_mut(index));
+        }
+        self
+    }
+
+    /// Appends `default` into the end of structure if value is missing,
+    /// then returns reference to the value.
+    pub fn or_insert(self, default: CfuaType) -> &'a mut CfuaType {
+        self.or_insert_with(|| default)
+    }
+
+    /// Appends result of `f` into the end of structure if value is missing,
+    /// then returns reference to the value.
+    pub fn or_insert_with<F>(self, f: F) -> &'a mut CfuaType
+    where F: FnOnce() -> CfuaType {
+        let index = match self.index {
+            Some(index) => index,
+            None => {
+                let index = self.data.entries().len();
+                self.data.insert_at(index, self.key, f());
+                index
+            },
+        };
+        self.data.value_at_mut(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CfuaWriter, FormatOptions};
+
+    use super::*;
+
+    fn example() -> Cfua {
+        Cfua::from_string("name: 'example\nport: 8080\ndebug: false\n").unwrap()
+    }
+
+    #[test]
+    fn set_keeps_order() {
+        let mut data = example();
+        data.set("port", 9000).unwrap();
+        data.set("workers", 4).unwrap();
+        data.set("debug", None::<bool>).unwrap();
+
+        assert_eq!(data.to_string(), "name: 'example\nport: 9000\nworkers: 4\n");
+    }
+
+    #[test]
+    fn repeated_keys() {
+        let mut data = Cfua::from_string("x: 1\ny: 2\nx: 3\nx: 4\n").unwrap();
+        data.set("x", 5).unwrap();
+        assert_eq!(data.to_string(), "x: 5\ny: 2\n");
+
+        let mut data = Cfua::from_string("x: 1\ny: 2\nx: 3\n").unwrap();
+        assert_eq!(data.remove("x"), Some(CfuaType::Integer(1)));
+        assert_eq!(data.to_string(), "y: 2\n");
+    }
+
+    #[test]
+    fn try_set_checks_key() {
+        let mut data = example();
+
+        assert!(matches!(data.try_set("Port", 1), Err(CfuaError::InvalidKey(key)) if key == "Port"));
+        assert!(data.try_set("-x", None::<i64>).is_err());
+        assert_eq!(data, example());
+
+        data.try_set("port", 9000).unwrap();
+        assert_eq!(data.read_integer("port"), Some(9000));
+    }
+
+    #[test]
+    fn set_resets_radix() {
+        let writer = CfuaWriter::new(FormatOptions { radix: None, ..Default::default() });
+        let mut data = Cfua::from_string("mask: hff\nflags: b101\n").unwrap();
+        data.set("mask", 10).unwrap();
+        assert_eq!(writer.format(&data).unwrap(), "mask: 10\nflags: b101\n");
+    }
+
+    #[test]
+    fn remove_and_rename() {
+        let mut data = example();
+
+        assert_eq!(data.remove("port"), Some(CfuaType::Integer(8080)));
+        assert_eq!(data.remove("port"), None);
+        data.rename("debug", "verbose").unwrap();
+        assert!(matches!(data.rename("debug", "quiet"), Err(CfuaError::KeyNotFound(_))));
+        assert!(matches!(data.rename("name", "verbose"), Err(CfuaError::DuplicateKey(_))));
+
+        assert_eq!(data.to_string(), "name: 'example\nverbose: false\n");
+    }
+
+    #[test]
+    fn insert_relative() {
+        let mut data = example();
+        data.insert_before("port", "host", "localhost").unwrap();
+        data.insert_after("debug", "trace", true).unwrap();
+
+        assert!(matches!(data.insert_after("missing", "x", 1), Err(CfuaError::KeyNotFound(_))));
+        assert!(matches!(data.insert_after("name", "port", 1), Err(CfuaError::DuplicateKey(_))));
+        assert!(matches!(data.insert_after("name", "x", None::<i64>), Err(CfuaError::Conversion { key, .. }) if key == "x"));
+        assert!(data.insert_after("name", "x", Vec::<i64>::new()).is_err());
+        assert_eq!(data.to_string(), "name: 'example\nhost: 'localhost\nport: 8080\ndebug: false\ntrace: true\n");
+    }
+
+    #[test]
+    fn entry_api() {
+        let mut data = example();
+
+        *data.entry("port").or_insert(CfuaType::Integer(80)) = CfuaType::Integer(443);
+        data.entry("timeout").or_insert_with(|| CfuaType::Float(1.5));
+        assert!(!data.entry("missing").and_modify(|_| unreachable!()).exists());
+
+        assert_eq!(data.to_string(), "name: 'example\nport: 443\ndebug: false\ntimeout: 1.5\n");
+    }
+}
@@ -0,0 +1,181 @@
+//! Pull-based parsing of cfua data.
+//!
+//! [`Events`] reads input line by line and yields [`Event`]s as soon
+//! as they are recognized, without building a [`Cfua`](crate::Cfua)
+//! structure. It can be used to scan large files, or to stop reading
+//! once the needed value is found.
+//!
+//! ## Example
+//!
+//! ```
+//! use cfua::{CfuaType, Event, Events};
+//!
+//! let input = "name: 'example\n% server settings\n@server\nport: 8080\n";
+//! let port = Events::new(input.as_bytes())
+//!     .map(Result::unwrap)
+//!     .skip_while(|event| *event != Event::Key("port".to_string()))
+//!     .nth(1);
+//!
+//! assert_eq!(port, Some(Event::Value(CfuaType::Integer(8080))));
+//! ```
+
+use std::{io::BufRead, iter::FusedIterator};
+
+use crate::{cfua::CfuaType, parser::ParserData, CfuaError};
+
+/// Single syntactic element of cfua data.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// Section declaration (`@name`). Following keys belong to this
+    /// section, up to the next section declaration.
+    SectionStart(String),
+    /// Key of a value. It is followed either by single [`Event::Value`],
+    /// or by an array enclosed in [`Event::ArrayStart`] and [`Event::ArrayEnd`].
+    Key(String),
+    /// Non-array value.
+    Value(CfuaType),
+    /// Beginning of an array.
+    ArrayStart,
+    /// Single element of an array.
+    ArrayElement(CfuaType),
+    /// End of an array.
+    ArrayEnd,
+    /// Text of a comment, without leading `%`.
+    Comment(String),
+}
+
+/// Iterator of [`Event`]s read from `reader`.
+///
+/// Input is read lazily, one line at a time. Iteration ends after
+/// the first error.
+pub struct Events<R> {
+    reader: R,
+    parser: ParserData,
+    line: String,
+    /// error which is yielded after events preceding it
+    error: Option<CfuaError>,
+    finished: bool,
+}
+
+impl<R> Events<R>
+where R: BufRead {
+    /// Creates iterator reading from `reader`. String input can be
+    /// read with `Events::new(string.as_bytes())`.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            parser: ParserData::new(),
+            line: String::with_capacity(256),
+            error: None,
+            finished: false,
+        }
+    }
+
+    /// Reads next line of input, queuing events found in it.
+    fn read_line(&mut self) -> Result<(), CfuaError> {
+        self.line.clear();
+        if self.reader.read_line(&mut self.line).map_err(CfuaError::IoError)? == 0 {
+            self.finished = true;
+            self.parser.end_input()
+        } else {
+            self.parser.feed_line(&self.line)
+        }
+    }
+}
+
+impl<R> Iterator for Events<R>
+where R: BufRead {
+    type Item = Result<Event, CfuaError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.parser.next_event() {
+                return Some(Ok(event));
+            }
+            if self.finished {
+                return self.error.take().map(Err);
+            }
+            if let Err(e) = self.read_line() {
+                self.finished = true;
+                self.error = Some(e);
+            }
+        }
+    }
+}
+
+impl<R> FusedIterator for Events<R>
+where R: BufRead {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events(input: &str) -> Vec<Event> {
+        Events::new(input.as_bytes()).collect::<Result<_, _>>().unwrap()
+    }
+
+    #[test]
+    fn event_order() {
+        let example =
+r"% header
+name: 'multi
+'line
+numbers: [1, 2]
+@section
+hosts: [
+#'alpha
+#'beta
+]
+% trailing";
+
+        assert_eq!(events(example), vec![
+            Event::Comment(" header".to_string()),
+            Event::Key("name".to_string()),
+            Event::Value(CfuaType::String("multi\nline".to_string())),
+            Event::Key("numbers".to_string()),
+            Event::ArrayStart,
+            Event::ArrayElement(CfuaType::Integer(1)),
+            Event::ArrayElement(CfuaType::Integer(2)),
+            Event::ArrayEnd,
+            Event::SectionStart("section".to_string()),
+            Event::Key("hosts".to_string()),
+            Event::ArrayStart,
+            Event::ArrayElement(CfuaType::String("alpha".to_string())),
+            Event::ArrayElement(CfuaType::String("beta".to_string())),
+            Event::ArrayEnd,
+            Event::Comment(" trailing".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn comments_around_multiline_strings() {
+        let example =
+r"a: 'x
+% inside
+'y
+% after
+b: 'z
+% last";
+
+        assert_eq!(events(example), vec![
+            Event::Key("a".to_string()),
+            Event::Comment(" inside".to_string()),
+            Event::Value(CfuaType::String("x\ny".to_string())),
+            Event::Comment(" after".to_string()),
+            Event::Key("b".to_string()),
+            Event::Value(CfuaType::String("z".to_string())),
+            Event::Comment(" last".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn stops_after_error() {
+        let mut events = Events::new("a: 1\nb: 1x\nc: 2\n".as_bytes());
+
+        assert_eq!(events.next().unwrap().unwrap(), Event::Key("a".to_string()));
+        assert_eq!(events.next().unwrap().unwrap(), Event::Value(CfuaType::Integer(1)));
+        assert_eq!(events.next().unwrap().unwrap(), Event::Key("b".to_string()));
+        assert!(matches!(events.next(), Some(Err(CfuaError::MalformedInteger(..)))));
+        assert!(events.next().is_none());
+    }
+}
@@ -0,0 +1,306 @@
+//! Conversion between cfua data and INI files.
+//!
+//! INI has no single specification; this module reads the common form:
+//! `[section]` headers, `key = value` (or `key: value`) lines, and full
+//! line comments starting with `;` or `#`, which are attached to the
+//! following key or section. Values are untyped text, so `true`, `false`
+//! and numbers which are written back unchanged (e.g. `42` or `1.5`,
+//! but not `007`) are read as booleans, integers and floats; any other
+//! value, including one in quotes, is read as a string.
+//!
+//! Both conversions return all constructs which could not be represented,
+//! as [`CfuaError::Conversion`], along with data which could. Reading
+//! reports lines which are not valid INI, renamed and invalid keys
+//! (see [`Cfua::from_ini`]), duplicate keys (the first value is kept)
+//! and comments at the end of file. Writing reports arrays, multiline
+//! strings and non-finite floats, which are left out.
+//!
+//! ## Example
+//!
+//! ```
+//! use cfua::Cfua;
+//!
+//! let (data, diagnostics) = Cfua::from_ini("; listen address\n[server]\nhost = example.com\nPort = 8080\n");
+//!
+//! assert_eq!(data.query("server.port").and_then(|v| v.as_integer()), Some(8080));
+//! assert_eq!(diagnostics[0].to_string(), "key renamed to 'port' (at 'server.Port')");
+//!
+//! let (ini, diagnostics) = data.to_ini();
+//! assert_eq!(ini, "; listen address\n[server]\nhost = example.com\nport = 8080\n");
+//! assert!(diagnostics.is_empty());
+//! ```
+
+use crate::{migrate::{diagnostic, import_key, join}, Cfua, CfuaError, CfuaType};
+
+impl Cfua {
+    /// Reads INI data, continuing past constructs which cannot be
+    /// represented. Returns data read, along with all diagnostics
+    /// (empty if whole input was converted).
+    ///
+    /// Keys and section names are lowercased, and `_`, `.` and spaces
+    /// in them are replaced with hyphens. Names which are still not
+    /// valid cfua keys (e.g. containing digits) are left out, along
+    /// with their values.
+    pub fn from_ini(input: &str) -> (Cfua, Vec<CfuaError>) {
+        let mut diagnostics = Vec::new();
+        let mut data = Cfua::create();
+        // sections with their comments, written after top-level values
+        let mut sections: Vec<(String, Cfua, Option<String>)> = Vec::new();
+        // index in `sections`, or `None` for top-level keys, and
+        // `Some(None)` inside of a section which was left out
+        let mut current: Option<Option<usize>> = None;
+        let mut comment: Option<String> = None;
+
+        for (number, line) in input.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(text) = line.strip_prefix([';', '#']) {
+                let text = text.strip_prefix(' ').unwrap_or(text);
+                match &mut comment {
+                    Some(comment) => {
+                        comment.push('\n');
+                        comment.push_str(text);
+                    },
+                    None => comment = Some(text.to_string()),
+                }
+            } else if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+                let name = name.trim();
+                let index = import_key(name, None, &mut diagnostics).and_then(|key| {
+                    if let Some(index) = sections.iter().position(|(section, ..)| *section == key) {
+                        return Some(index);
+                    }
+                    if data.read_value(&key).is_some() {
+                        diagnostics.push(diagnostic(name, "section has the same name as another key"));
+                        return None;
+                    }
+                    sections.push((key, Cfua::create(), comment.take()));
+                    Some(sections.len() - 1)
+                });
+                comment = None;
+                current = Some(index);
+            } else if let Some(split) = line.find(['=', ':']) {
+                let (name, value) = (line[..split].trim(), &line[split + 1..]);
+                let comment = comment.take();
+                let (section, target) = match current {
+                    None => (None, &mut data),
+                    Some(Some(index)) => {
+                        let (name, section, _) = &mut sections[index];
+                        (Some(name.as_str()), section)
+                    },
+                    Some(None) => continue,
+                };
+
+                let Some(key) = import_key(name, section, &mut diagnostics) else {
+                    continue;
+                };
+                if target.read_value(&key).is_some() {
+                    diagnostics.push(diagnostic(&join(section, name), "duplicate key is left out"));
+                    continue;
+                }
+                target.write_value(key, import_value(value));
+                target.set_comment_at(target.len() - 1, comment);
+            } else {
+                diagnostics.push(diagnostic("", format!("line {}: expected section or `key = value`", number + 1)));
+            }
+        }
+
+        if comment.is_some() {
+            diagnostics.push(diagnostic("", "comment at the end of file is left out"));
+        }
+        for (name, section, comment) in sections {
+            if data.read_value(&name).is_some() {
+                diagnostics.push(diagnostic(&name, "section has the same name as another key"));
+            } else {
+                // INI sections hold only values, so there is nothing to nest
+                data.write_value(name, CfuaType::Section(section));
+                data.set_comment_at(data.len() - 1, comment);
+            }
+        }
+        (data, diagnostics)
+    }
+
+    /// Writes data as INI, leaving out values which cannot be
+    /// represented. Returns INI text, along with all diagnostics
+    /// (empty if all data was written). Top-level values are written
+    /// before sections, and comments are written as `;` lines.
+    pub fn to_ini(&self) -> (String, Vec<CfuaError>) {
+        let mut diagnostics = Vec::new();
+        let mut output = String::new();
+
+        for (key, value) in self.iter().filter(|(_, value)| value.as_section().is_none()) {
+            write_entry(&mut output, self, None, key, value, &mut diagnostics);
+        }
+        for (name, value) in self {
+            let Some(section) = value.as_section() else {
+                continue;
+            };
+            write_comment(&mut output, self.read_comment(name));
+            output.push_str(&format!("[{name}]\n"));
+            for (key, value) in section {
+                write_entry(&mut output, section, Some(name), key, value, &mut diagnostics);
+            }
+        }
+        (output, diagnostics)
+    }
+}
+
+/// Converts text of INI value into cfua value.
+fn import_value(text: &str) -> CfuaType {
+    let text = text.trim();
+    for quote in ['"', '\''] {
+        if let Some(inner) = text.strip_prefix(quote).and_then(|text| text.strip_suffix(quote)) {
+            return CfuaType::String(inner.to_string());
+        }
+    }
+
+    match text {
+        "true" => return CfuaType::Bool(true),
+        "false" => return CfuaType::Bool(false),
+        _ => {},
+    }
+    // numbers are only recognized if they are written back unchanged,
+    // so that e.g. `007` or `1.10` stay strings
+    if let Ok(value) = text.parse::<i64>()
+        && value.to_string() == text {
+        return CfuaType::Integer(value);
+    }
+    if let Ok(value) = text.parse::<u64>()
+        && value.to_string() == text {
+        return CfuaType::Unsigned(value);
+    }
+    if let Ok(value) = text.parse::<f64>()
+        && format!("{value:?}") == text {
+        return CfuaType::Float(value);
+    }
+    CfuaType::String(text.to_string())
+}
+
+/// Returns text of INI value which is read back as `value`,
+/// or `None` if there is no such text.
+fn export_value(value: &CfuaType) -> Option<String> {
+    let text = match value {
+        CfuaType::String(value) if value.contains(['\n', '\r']) => return None,
+        CfuaType::String(value) => match import_value(value) {
+            CfuaType::String(read) if read == *value => value.clone(),
+            _ => format!("\"{value}\""),
+        },
+        CfuaType::Float(value) => format!("{value:?}"),
+        CfuaType::Array(_) |
+        CfuaType::Section(_) => return None,
+        other => other.to_string(),
+    };
+    (import_value(&text) == *value).then_some(text)
+}
+
+fn write_entry(output: &mut String, data: &Cfua, section: Option<&str>, key: &str, value: &CfuaType, diagnostics: &mut Vec<CfuaError>) {
+    match export_value(value) {
+        Some(text) => {
+            write_comment(output, data.read_comment(key));
+            output.push_str(&format!("{key} = {text}\n"));
+        },
+        None => diagnostics.push(diagnostic(&join(section, key), format!("{} value cannot be represented in INI", value.type_name()))),
+    }
+}
+
+fn write_comment(output: &mut String, comment: Option<&str>) {
+    for line in comment.into_iter().flat_map(|comment| comment.split('\n')) {
+        output.push_str(&format!("; {line}\n"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_ini() {
+        let input =
+r#"; global settings
+name = example
+debug: true
+
+[Server]
+# listen port
+port = 8080
+ratio = 0.5
+version = 1.10
+zip = 007
+quoted = "  spaced  "
+port = 9090
+bad line
+Max_Conns = 10
+
+[2fa]
+enabled = true
+[server]
+host = localhost
+; dangling
+"#;
+        let (mut data, diagnostics) = Cfua::from_ini(input);
+
+        let expected = Cfua::from_string(
+r"% global settings
+name: 'example
+debug: true
+@server
+% listen port
+port: 8080
+ratio: 0.5
+version: '1.10
+zip: '007
+max-conns: 10
+host: 'localhost
+").unwrap();
+        assert_eq!(data.query("server.quoted").and_then(CfuaType::as_str), Some("  spaced  "));
+        data.entry("server").and_modify(|server| if let CfuaType::Section(server) = server {
+            server.remove("quoted");
+        });
+        assert_eq!(data, expected);
+        assert_eq!(data.read_comment("name"), Some("global settings"));
+        assert_eq!(data.read_section_ref("server").unwrap().read_comment("port"), Some("listen port"));
+
+        let messages: Vec<_> = diagnostics.iter().map(ToString::to_string).collect();
+        assert_eq!(messages, vec![
+            "key renamed to 'server' (at 'Server')",
+            "duplicate key is left out (at 'server.port')",
+            "line 13: expected section or `key = value`",
+            "key renamed to 'max-conns' (at 'server.Max_Conns')",
+            "name cannot be converted into cfua key (at '2fa')",
+            "comment at the end of file is left out",
+        ]);
+    }
+
+    #[test]
+    fn write_ini() {
+        let mut data = Cfua::from_string(
+r"name: 'true
+flags: [1, 2]
+@server
+port: 8080
+ratio: 2.0
+note: 'first
+'second
+").unwrap();
+        data.write_float("big", 1e300);
+        data.write_float("nan", f64::NAN);
+        data.write_comment("server", "server settings").unwrap();
+
+        let (output, diagnostics) = data.to_ini();
+        assert_eq!(output, "name = \"true\"\nbig = 1e300\n; server settings\n[server]\nport = 8080\nratio = 2.0\n");
+
+        let messages: Vec<_> = diagnostics.iter().map(ToString::to_string).collect();
+        assert_eq!(messages, vec![
+            "array value cannot be represented in INI (at 'flags')",
+            "float value cannot be represented in INI (at 'nan')",
+            "string value cannot be represented in INI (at 'server.note')",
+        ]);
+
+        let (read, diagnostics) = Cfua::from_ini(&output);
+        assert!(diagnostics.is_empty());
+        assert_eq!(read.read_string("name"), Some("true".to_string()));
+        assert_eq!(read.read_float("big"), Some(1e300));
+    }
+}
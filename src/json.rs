@@ -0,0 +1,241 @@
+//! Conversion between cfua data and JSON, available with `json` feature.
+//!
+//! Sections are converted into nested objects, and arrays into JSON
+//! arrays. Comments are not carried over. Values which cannot be
+//! represented on the other side cause [`CfuaError::Conversion`]:
+//! - NaN and infinite floats, and integers outside of `i64` and `u64`
+//!   range, cannot be converted into JSON,
+//! - `null`, nested arrays, objects inside of arrays or sections,
+//!   and arrays mixing values of different types cannot be converted
+//!   into cfua. As JSON does not distinguish integers from floats,
+//!   an array mixing them is converted into array of floats, unless
+//!   some of its integers cannot be represented exactly as float.
+//!
+//! ## Example
+//!
+//! ```
+//! use cfua::Cfua;
+//!
+//! let data = Cfua::from_string("name: 'example\n@server\nport: 8080\n").unwrap();
+//! let json = data.to_json().unwrap();
+//!
+//! assert_eq!(json.to_string(), r#"{"name":"example","server":{"port":8080}}"#);
+//! assert_eq!(Cfua::from_json(&json).unwrap(), data);
+//! ```
+
+use serde_json::{Map, Number, Value};
+
+use crate::{Cfua, CfuaError, CfuaType};
+
+fn error<M>(message: M) -> CfuaError
+where M: ToString {
+    CfuaError::Conversion { key: String::new(), message: message.to_string() }
+}
+
+/// Returns name of JSON value type, for error messages.
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+impl Cfua {
+    /// Converts data into JSON object, with sections as nested objects.
+    /// Returns an error if data contains a value which JSON cannot hold
+    /// (see [module documentation](crate::json)).
+    pub fn to_json(&self) -> Result<Value, CfuaError> {
+        let mut object = Map::new();
+        for (key, value) in self {
+            object.insert(key.to_string(), value.to_json().map_err(|e| e.at_key(key))?);
+        }
+        Ok(Value::Object(object))
+    }
+
+    /// Converts JSON object into cfua data. Values which are objects
+    /// become sections. Returns an error if `value` is not an object,
+    /// if it contains a value which cfua cannot hold (see [module
+    /// documentation](crate::json)), or if any key is not a valid
+    /// cfua key.
+    pub fn from_json(value: &Value) -> Result<Cfua, CfuaError> {
+        match value {
+            Value::Object(object) => from_object(object, false),
+            other => Err(error(format!("expected object, found {}", json_type(other)))),
+        }
+    }
+}
+
+/// Converts `object` into cfua data. Objects inside of a `section`
+/// are rejected, as sections cannot be nested.
+fn from_object(object: &Map<String, Value>, section: bool) -> Result<Cfua, CfuaError> {
+    let mut data = Cfua::create();
+    for (key, value) in object {
+        let value = match value {
+            Value::Object(_) if section => Err(error("nested sections are not allowed")),
+            Value::Object(inner) => from_object(inner, true).map(CfuaType::Section),
+            other => CfuaType::from_json(other),
+        };
+        data.try_write_value(key, value.map_err(|e| e.at_key(key))?)?;
+    }
+    Ok(data)
+}
+
+impl CfuaType {
+    /// Converts value into JSON. Returns an error if value (or any
+    /// of its elements) cannot be held by JSON.
+    pub fn to_json(&self) -> Result<Value, CfuaError> {
+        Ok(match self {
+            CfuaType::Integer(value) => Value::from(*value),
+            CfuaType::Unsigned(value) => Value::from(*value),
+            #[cfg(feature = "bigint")]
+            CfuaType::BigInt(value) => return Err(error(format!("integer {value} is out of range for JSON number"))),
+            CfuaType::Float(value) => match Number::from_f64(*value) {
+                Some(number) => Value::Number(number),
+                None => return Err(error(format!("float {value} cannot be represented in JSON"))),
+            },
+            CfuaType::String(value) => Value::String(value.clone()),
+            CfuaType::Bool(value) => Value::Bool(*value),
+            CfuaType::Array(elements) => {
+                let elements = elements.iter().enumerate()
+                    .map(|(index, element)| element.to_json().map_err(|e| e.at_key(&format!("[{index}]"))))
+                    .collect::<Result<_, _>>()?;
+                Value::Array(elements)
+            },
+            CfuaType::Section(section) => section.to_json()?,
+        })
+    }
+
+    /// Converts JSON value into cfua value. Objects become sections.
+    /// Returns an error if `value` cannot be held by cfua.
+    pub fn from_json(value: &Value) -> Result<CfuaType, CfuaError> {
+        match value {
+            Value::Null => Err(error("null cannot be represented in cfua")),
+            Value::Bool(value) => Ok(CfuaType::Bool(*value)),
+            Value::Number(number) => Ok(from_number(number)),
+            Value::String(value) => Ok(CfuaType::String(value.clone())),
+            Value::Array(elements) => from_array(elements),
+            Value::Object(object) => from_object(object, true).map(CfuaType::Section),
+        }
+    }
+}
+
+fn from_number(number: &Number) -> CfuaType {
+    if let Some(value) = number.as_i64() {
+        CfuaType::Integer(value)
+    } else if let Some(value) = number.as_u64() {
+        CfuaType::Unsigned(value)
+    } else {
+        // without `arbitrary_precision` feature of serde_json,
+        // every number is representable as f64
+        CfuaType::Float(number.as_f64().unwrap_or(f64::NAN))
+    }
+}
+
+fn from_array(elements: &[Value]) -> Result<CfuaType, CfuaError> {
+    let mut array = Vec::with_capacity(elements.len());
+    for (index, element) in elements.iter().enumerate() {
+        let value = match element {
+            Value::Array(_) => Err(error("nested arrays are not allowed")),
+            Value::Object(_) => Err(error("objects inside of arrays are not allowed")),
+            other => CfuaType::from_json(other),
+        };
+        array.push(value.map_err(|e| e.at_key(&format!("[{index}]")))?);
+    }
+
+    if array.iter().any(|value| matches!(value, CfuaType::Float(_))) {
+        for (index, value) in array.iter_mut().enumerate() {
+            let float = match *value {
+                CfuaType::Integer(integer) => exact_float(integer.into()),
+                CfuaType::Unsigned(integer) => exact_float(integer.into()),
+                _ => continue,
+            };
+            let Some(float) = float else {
+                let message = format!("integer {value} in array of floats cannot be converted into float exactly");
+                return Err(error(message).at_key(&format!("[{index}]")));
+            };
+            *value = CfuaType::Float(float);
+        }
+    }
+
+    if let Some(first) = array.first()
+        && let Some(other) = array.iter().find(|value| !value.has_same_type(first)) {
+        return Err(error(format!("array mixes {} and {} values", first.type_name(), other.type_name())));
+    }
+    Ok(CfuaType::Array(array))
+}
+
+/// Converts `integer` into float, or returns `None` if it would lose precision.
+fn exact_float(integer: i128) -> Option<f64> {
+    let float = integer as f64;
+    (float as i128 == integer).then_some(float)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let data = Cfua::from_string(
+r"name: 'example
+ratio: 0.5
+big: 18446744073709551615
+limits: [1.5, 2.0]
+@server
+hosts: [
+#'alpha
+#'beta
+]
+").unwrap();
+        let json = data.to_json().unwrap();
+
+        assert_eq!(json, json!({
+            "name": "example",
+            "ratio": 0.5,
+            "big": u64::MAX,
+            "limits": [1.5, 2.0],
+            "server": { "hosts": ["alpha", "beta"] },
+        }));
+        assert_eq!(Cfua::from_json(&json).unwrap(), data);
+    }
+
+    #[test]
+    fn unrepresentable_values() {
+        let data = Cfua::from_string("@server\nratios: [1.0, inf]\n").unwrap();
+        let Err(CfuaError::Conversion { key, .. }) = data.to_json() else { panic!() };
+        assert_eq!(key, "server.ratios[1]");
+        assert!(CfuaType::Float(f64::NAN).to_json().is_err());
+
+        let from = |value: Value| Cfua::from_json(&value);
+        assert!(from(json!([1])).is_err());
+        assert!(from(json!({ "x": null })).is_err());
+        assert!(from(json!({ "x": [[1]] })).is_err());
+        assert!(from(json!({ "x": [{}] })).is_err());
+        assert!(from(json!({ "x": { "y": {} } })).is_err());
+        assert!(matches!(from(json!({ "Bad": 1 })), Err(CfuaError::InvalidKey(_))));
+
+        let result = from(json!({ "x": [1, "a"] }));
+        assert!(matches!(result, Err(CfuaError::Conversion { key, message }) if key == "x" && message == "array mixes integer and string values"));
+    }
+
+    #[test]
+    fn numbers_in_arrays() {
+        let data = Cfua::from_json(&json!({ "x": [1, 2.5], "y": [1, u64::MAX] })).unwrap();
+
+        assert_eq!(data.read_array("x"), Some(vec![CfuaType::Float(1.0), CfuaType::Float(2.5)]));
+        assert_eq!(data.read_array("y"), Some(vec![CfuaType::Integer(1), CfuaType::Unsigned(u64::MAX)]));
+
+        let data = Cfua::from_json(&json!({ "x": [1_i64 << 53, 0.5] })).unwrap();
+        assert_eq!(data.read_array("x"), Some(vec![CfuaType::Float(9007199254740992.0), CfuaType::Float(0.5)]));
+        for json in [json!({ "x": [0.5, (1_i64 << 53) + 1] }), json!({ "x": [0.5, i64::MAX] }), json!({ "x": [0.5, u64::MAX] })] {
+            let result = Cfua::from_json(&json);
+            assert!(matches!(result, Err(CfuaError::Conversion { ref key, .. }) if key == "x[1]"), "{json}");
+        }
+    }
+}
@@ -0,0 +1,432 @@
+//! Layering of configuration sources.
+//!
+//! [`Layers`] merges cfua files (or data built in code), environment
+//! variables and `key=value` command-line arguments into a single
+//! [`Cfua`]. Sources are applied in the order in which they are added,
+//! and each one overrides values of the previous ones, so defaults
+//! should be added first, and command-line arguments last. Files and
+//! data are merged with [`Cfua::merge`], replacing values one by one:
+//! a section of a later file replaces only the keys it contains.
+//!
+//! Text of environment variables and arguments is converted into type
+//! of the value it overrides:
+//! - strings take the text as is,
+//! - integers, floats and booleans accept cfua literals of their type
+//!   (e.g. `h1f90`); floats accept integers as well,
+//! - arrays accept comma-separated elements (e.g. `alpha,beta`), each
+//!   converted into type of the first existing element, or a cfua array
+//!   literal; empty text is rejected, as arrays cannot be empty.
+//!
+//! Text which cannot be converted causes [`CfuaError::InvalidOverride`].
+//! Values which do not exist yet are read as cfua literals, or as
+//! strings if the text is not a valid literal.
+//!
+//! ## Example
+//!
+//! ```
+//! use cfua::{layer::{Layers, Source}, Cfua};
+//!
+//! let defaults = Cfua::from_string("debug: false\n@server\nport: 8080\nhosts: [\n#'localhost\n]\n").unwrap();
+//! let env = [("APP_SERVER_PORT".to_string(), "9000".to_string())];
+//!
+//! let mut layers = Layers::new();
+//! layers.add_data("defaults", defaults);
+//! layers.add_vars("APP", env).unwrap();
+//! layers.add_args(["debug=true", "server.hosts=alpha,beta"]).unwrap();
+//!
+//! let data = layers.data();
+//! assert_eq!(data.query("server.port").and_then(|v| v.as_integer()), Some(9000));
+//! assert_eq!(data.query("server.hosts[1]").and_then(|v| v.as_str()), Some("beta"));
+//! assert_eq!(layers.source("server.port"), Some(&Source::Env("APP_SERVER_PORT".to_string())));
+//! assert_eq!(layers.source("debug").unwrap().to_string(), "argument 'debug=true'");
+//! ```
+
+use std::{collections::HashMap, env, fmt, path::{Path, PathBuf}};
+
+use crate::{cfua::{check_key, CfuaType}, Cfua, CfuaError, MergeStrategy, OnConflict};
+
+/// Source of a value in [`Layers`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// File with given path.
+    File(PathBuf),
+    /// Data added with [`Layers::add_data`], with given name.
+    Data(String),
+    /// Environment variable with given name.
+    Env(String),
+    /// Command-line argument, holding whole `key=value` pair.
+    Arg(String),
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::File(path) => write!(f, "file '{}'", path.display()),
+            Source::Data(name) => write!(f, "{name}"),
+            Source::Env(name) => write!(f, "environment variable {name}"),
+            Source::Arg(arg) => write!(f, "argument '{arg}'"),
+        }
+    }
+}
+
+/// Cfua data merged from several sources, remembering which source
+/// each value came from. See [module documentation](self) for details.
+///
+/// Values are addressed with paths: `key` for top-level values,
+/// and `section.key` for values inside a section.
+#[derive(Debug, Clone)]
+pub struct Layers {
+    data: Cfua,
+    sources: HashMap<String, Source>,
+    env_mapping: HashMap<String, String>,
+}
+
+impl Default for Layers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Layers {
+    /// Creates empty layers.
+    pub fn new() -> Self {
+        Self {
+            data: Cfua::create(),
+            sources: HashMap::new(),
+            env_mapping: HashMap::new(),
+        }
+    }
+
+    /// Returns merged data.
+    pub fn data(&self) -> &Cfua {
+        &self.data
+    }
+
+    /// Returns merged data, dropping information about sources.
+    pub fn into_data(self) -> Cfua {
+        self.data
+    }
+
+    /// Returns source of value at `path`, or `None` if there is no
+    /// such value.
+    pub fn source(&self, path: &str) -> Option<&Source> {
+        self.sources.get(path)
+    }
+
+    /// Returns paths of all values along with their sources, in order
+    /// of values in merged data.
+    pub fn sources(&self) -> Vec<(String, &Source)> {
+        self.data.value_paths().into_iter()
+            .filter_map(|path| {
+                let source = self.sources.get(&path)?;
+                Some((path, source))
+            })
+            .collect()
+    }
+
+    /// Merges `data` over current values. `name` describes the data
+    /// in [`Source::Data`] (e.g. `defaults`).
+    pub fn add_data<N>(&mut self, name: N, data: Cfua)
+    where N: ToString {
+        self.merge(data, Source::Data(name.to_string()));
+    }
+
+    /// Reads file with specified `path` and merges it over current values.
+    /// Returns the same errors as [`Cfua::from_file_path`].
+    pub fn add_file<P>(&mut self, path: P) -> Result<(), CfuaError>
+    where P: AsRef<Path> {
+        let data = Cfua::from_file_path(&path)?;
+        self.merge(data, Source::File(path.as_ref().to_path_buf()));
+        Ok(())
+    }
+
+    /// Makes environment variable `name` override value at `path`,
+    /// regardless of prefix passed to [`add_env`](Self::add_env).
+    /// Mapped values don't need to exist before.
+    pub fn map_env<N, P>(&mut self, name: N, path: P)
+    where N: ToString,
+          P: ToString {
+        self.env_mapping.insert(name.to_string(), path.to_string());
+    }
+
+    /// Overrides values with environment variables named `PREFIX_PATH`,
+    /// where `PATH` is path of an existing value in uppercase, with dots
+    /// and hyphens replaced by underscores (e.g. `APP_SERVER_MAX_CONNS`
+    /// for `server.max-conns` with prefix `APP`). Variables which don't
+    /// match any existing value are ignored, unless they are mapped with
+    /// [`map_env`](Self::map_env). With empty prefix, variables are
+    /// matched by `PATH` alone.
+    ///
+    /// Returns an error if value of a variable cannot be converted,
+    /// or [`CfuaError::AmbiguousVariable`] if a variable matches more
+    /// than one value (e.g. both `a.b-c` and `a-b.c`), in which case
+    /// it should be mapped with [`map_env`](Self::map_env).
+    pub fn add_env(&mut self, prefix: &str) -> Result<(), CfuaError> {
+        self.add_vars(prefix, env::vars())
+    }
+
+    /// Like [`add_env`](Self::add_env), but reads variables from `vars`
+    /// (pairs of name and value) instead of process environment.
+    pub fn add_vars<I>(&mut self, prefix: &str, vars: I) -> Result<(), CfuaError>
+    where I: IntoIterator<Item = (String, String)> {
+        // variables are applied in order of their names,
+        // so that result doesn't depend on environment order
+        let mut vars: Vec<_> = vars.into_iter().collect();
+        vars.sort();
+
+        for (name, value) in vars {
+            let path = match self.env_mapping.get(&name) {
+                Some(path) => path.clone(),
+                None => {
+                    let rest = if prefix.is_empty() {
+                        Some(name.as_str())
+                    } else {
+                        name.strip_prefix(prefix).and_then(|rest| rest.strip_prefix('_'))
+                    };
+                    let Some(rest) = rest else {
+                        continue;
+                    };
+                    let mut paths: Vec<_> = self.data.value_paths().into_iter()
+                        .filter(|path| env_name(path) == rest)
+                        .collect();
+                    match paths.len() {
+                        0 => continue,
+                        1 => paths.remove(0),
+                        _ => return Err(CfuaError::AmbiguousVariable { name, paths }),
+                    }
+                },
+            };
+            self.apply(&path, &value, Source::Env(name))?;
+        }
+        Ok(())
+    }
+
+    /// Overrides values with `args` in form `path=value`, e.g.
+    /// `server.port=9000`. Returns [`CfuaError::InvalidKey`] if an
+    /// argument is not such pair, or an error if its value cannot
+    /// be converted.
+    pub fn add_args<I, S>(&mut self, args: I) -> Result<(), CfuaError>
+    where I: IntoIterator<Item = S>,
+          S: AsRef<str> {
+        for arg in args {
+            let arg = arg.as_ref();
+            let Some((path, value)) = arg.split_once('=') else {
+                return Err(CfuaError::InvalidKey(arg.to_string()));
+            };
+            self.apply(path.trim(), value, Source::Arg(arg.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Merges values of `data` over current values with [`Cfua::merge`],
+    /// recording `source` of each of them.
+    fn merge(&mut self, data: Cfua, source: Source) {
+        for (key, value) in &data {
+            // sections are merged key by key, while any other value
+            // replaces whole existing one, along with its sources
+            if let (CfuaType::Section(_), Some(CfuaType::Section(_))) = (value, self.data.read_value(key)) {
+                continue;
+            }
+            let prefix = format!("{key}.");
+            self.sources.retain(|path, _| path != key && !path.starts_with(&prefix));
+        }
+        for path in data.value_paths() {
+            self.sources.insert(path, source.clone());
+        }
+
+        let strategy = MergeStrategy { on_conflict: OnConflict::Replace, ..Default::default() };
+        // only `OnConflict::Error` makes merging fail
+        let _ = self.data.merge(data, strategy);
+    }
+
+    /// Sets value at `path` to `text` converted into type of current value.
+    fn apply(&mut self, path: &str, text: &str, source: Source) -> Result<(), CfuaError> {
+        let (section, key) = match path.split_once('.') {
+            Some((section, key)) => (Some(section), key),
+            None => (None, path),
+        };
+        check_key(key)?;
+        if let Some(section) = section {
+            check_key(section)?;
+        }
+
+        let value = coerce(text, self.data.query(path)).map_err(|expected| CfuaError::InvalidOverride {
+            key: path.to_string(),
+            value: text.to_string(),
+            expected,
+            source: source.to_string(),
+        })?;
+
+        let target = match section {
+            None => &mut self.data,
+            Some(section) => match self.data.entry(section).or_insert_with(|| CfuaType::Section(Cfua::create())) {
+                CfuaType::Section(target) => target,
+                other => return Err(CfuaError::TypeMismatch { key: section.to_string(), expected: "section", found: other.type_name() }),
+            },
+        };
+        target.set(key, value)?;
+        self.sources.insert(path.to_string(), source);
+        Ok(())
+    }
+}
+
+/// Returns name of environment variable (without prefix) for `path`.
+fn env_name(path: &str) -> String {
+    path.to_ascii_uppercase().replace(['.', '-'], "_")
+}
+
+/// Reads `text` as single cfua value, as it would be written after key.
+fn literal(text: &str) -> Option<CfuaType> {
+    if text.contains(['\n', '\r']) {
+        return None;
+    }
+    Cfua::from_string(format!("value: {text}\n")).ok()?
+        .read_value("value").cloned()
+}
+
+/// Converts `text` into type of `current` value, or reads it as literal
+/// (or string) if there is no current value. Returns name of expected
+/// type if `text` cannot be converted.
+fn coerce(text: &str, current: Option<&CfuaType>) -> Result<CfuaType, &'static str> {
+    match current {
+        None => Ok(literal(text).unwrap_or_else(|| CfuaType::String(text.to_string()))),
+        Some(CfuaType::String(_)) => Ok(CfuaType::String(text.to_string())),
+        Some(CfuaType::Array(elements)) => {
+            if text.trim_start().starts_with('[') {
+                return match literal(text) {
+                    Some(value @ CfuaType::Array(_)) => Ok(value),
+                    _ => Err("array"),
+                };
+            }
+            if text.trim().is_empty() {
+                return Err("array");
+            }
+            text.split(',')
+                .map(|element| coerce(element.trim(), elements.first()))
+                .collect::<Result<_, _>>()
+                .map(CfuaType::Array)
+        },
+        Some(current) => match (current, literal(text)) {
+            (_, Some(value)) if value.has_same_type(current) => Ok(value),
+            (CfuaType::Float(_), Some(CfuaType::Integer(value))) => Ok(CfuaType::Float(value as f64)),
+            (CfuaType::Float(_), Some(CfuaType::Unsigned(value))) => Ok(CfuaType::Float(value as f64)),
+            _ => Err(current.type_name()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CfuaWriter, FormatOptions};
+
+    use super::*;
+
+    fn vars(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn precedence() {
+        let mut layers = Layers::new();
+        layers.add_data("defaults", Cfua::from_string("name: 'example\n@server\nport: 8080\nmax-conns: 10\n").unwrap());
+        layers.add_data("site", Cfua::from_string("@server\n% more for production\nmax-conns: 100\n").unwrap());
+        layers.add_vars("APP", vars(&[
+            ("APP_SERVER_PORT", "9000"),
+            ("APP_SERVER_MAX_CONNS", "200"),
+            ("APP_UNKNOWN", "1"),
+            ("OTHER_NAME", "ignored"),
+        ])).unwrap();
+        layers.add_args(["server.port=9001"]).unwrap();
+
+        let expected = Cfua::from_string("name: 'example\n@server\nport: 9001\nmax-conns: 200\n").unwrap();
+        assert_eq!(*layers.data(), expected);
+        assert_eq!(layers.data().read_section_ref("server").unwrap().read_comment("max-conns"), Some("more for production"));
+
+        let sources: Vec<_> = layers.sources().into_iter()
+            .map(|(path, source)| format!("{path}: {source}"))
+            .collect();
+        assert_eq!(sources, vec![
+            "name: defaults",
+            "server.port: argument 'server.port=9001'",
+            "server.max-conns: environment variable APP_SERVER_MAX_CONNS",
+        ]);
+
+        // section replacing a value drops its source
+        layers.add_data("replacement", Cfua::from_string("name: 'other\nserver: 1\n").unwrap());
+        assert_eq!(layers.source("server.port"), None);
+        assert_eq!(layers.source("server"), Some(&Source::Data("replacement".to_string())));
+    }
+
+    #[test]
+    fn merged_values_keep_their_radix() {
+        let mut layers = Layers::new();
+        layers.add_data("defaults", Cfua::from_string("mask: hff\nmode: 1\n@server\nflags: b101\n").unwrap());
+        layers.add_data("site", Cfua::from_string("mask: 16\nmode: h2\n@server\nflags: 3\n").unwrap());
+
+        let writer = CfuaWriter::new(FormatOptions { radix: None, ..Default::default() });
+        assert_eq!(writer.format(layers.data()).unwrap(), "mask: 16\nmode: h2\n@server\nflags: 3\n");
+    }
+
+    #[test]
+    fn coercion() {
+        let mut layers = Layers::new();
+        layers.add_data("defaults", Cfua::from_string(
+r"version: '1
+mask: 0
+ratio: 0.5
+debug: false
+ports: [80]
+hosts: [
+#'localhost
+]
+").unwrap());
+        layers.map_env("PORT", "server.port");
+        layers.add_vars("APP", vars(&[("APP_VERSION", "2"), ("APP_MASK", "h10"), ("PORT", "9000")])).unwrap();
+        layers.add_args(["ratio=3", "ports=80, 443", "hosts=alpha,beta", "new=true", "other=some text"]).unwrap();
+
+        let data = layers.data();
+        let expected = Cfua::from_string(
+r"version: '2
+mask: h10
+ratio: 3.0
+debug: false
+ports: [80, 443]
+hosts: [
+#'alpha
+#'beta
+]
+new: true
+other: 'some text
+").unwrap();
+        for (key, value) in &expected {
+            assert_eq!(data.read_value(key), Some(value));
+        }
+        assert_eq!(data.query("server.port"), Some(&CfuaType::Integer(9000)));
+
+        let Err(error) = layers.add_args(["debug=yes"]) else { panic!() };
+        assert_eq!(error.to_string(), "cannot override 'debug' with 'yes' from argument 'debug=yes': expected boolean");
+        assert!(matches!(layers.add_args(["ports=80,x"]), Err(CfuaError::InvalidOverride { expected: "integer", .. })));
+        assert!(matches!(layers.add_args(["ports="]), Err(CfuaError::InvalidOverride { expected: "array", .. })));
+        assert!(matches!(layers.add_vars("APP", vars(&[("APP_HOSTS", " ")])), Err(CfuaError::InvalidOverride { expected: "array", .. })));
+        assert!(layers.data().validate().is_ok());
+        assert!(matches!(layers.add_args(["server=1"]), Err(CfuaError::InvalidOverride { expected: "section", .. })));
+        assert!(matches!(layers.add_args(["debug"]), Err(CfuaError::InvalidKey(_))));
+        assert!(matches!(layers.add_args(["Debug=true"]), Err(CfuaError::InvalidKey(_))));
+        assert!(matches!(layers.add_args(["debug.x=1"]), Err(CfuaError::TypeMismatch { .. })));
+    }
+
+    #[test]
+    fn ambiguous_variables() {
+        let mut layers = Layers::new();
+        layers.add_data("defaults", Cfua::from_string("max-conns: 1\n@max\nconns: 2\n").unwrap());
+
+        let Err(error) = layers.add_vars("APP", vars(&[("APP_MAX_CONNS", "3")])) else { panic!() };
+        assert!(matches!(&error, CfuaError::AmbiguousVariable { name, paths } if name == "APP_MAX_CONNS" && paths.len() == 2));
+        assert_eq!(error.to_string(), "environment variable APP_MAX_CONNS matches more than one value: 'max-conns', 'max.conns'");
+        assert_eq!(layers.data().read_integer("max-conns"), Some(1));
+
+        layers.map_env("APP_MAX_CONNS", "max.conns");
+        layers.add_vars("APP", vars(&[("APP_MAX_CONNS", "3")])).unwrap();
+        assert_eq!(layers.data().query("max.conns"), Some(&CfuaType::Integer(3)));
+    }
+}
@@ -1,11 +1,40 @@
 mod cfua;
 pub use cfua::Cfua;
 pub use cfua::CfuaType;
+pub use cfua::Iter;
 
 pub mod array;
+pub mod convert;
+pub use convert::{ArrayElement, FromCfua, IntoCfua};
 
 mod read;
 mod write;
+pub use write::{ArrayStyle, CfuaWriter, FormatOptions, Radix};
+mod path;
+mod edit;
+pub use edit::Entry;
 
 mod parser;
-pub use parser::CfuaError;
\ No newline at end of file
+mod events;
+pub use events::{Event, Events};
+pub mod document;
+pub use document::Document;
+pub mod ini;
+pub mod layer;
+mod merge;
+pub use merge::{ArrayMerge, MergeStrategy, OnConflict};
+mod migrate;
+pub use parser::{CfuaError, Span};
+
+#[cfg(feature = "serde")]
+pub mod de;
+#[cfg(feature = "serde")]
+pub mod ser;
+#[cfg(feature = "serde")]
+pub use de::{from_cfua, from_str, Deserializer};
+#[cfg(feature = "serde")]
+pub use ser::{to_cfua, to_string, Serializer};
+#[cfg(feature = "json")]
+pub mod json;
+#[cfg(feature = "toml")]
+pub mod toml;
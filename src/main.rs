@@ -0,0 +1,259 @@
+//! `cfua` command-line tool, for checking, querying and editing
+//! cfua files from shell scripts.
+
+use std::{env, fmt, fs, process::ExitCode};
+
+use cfua::{Cfua, CfuaError, CfuaType, Document};
+
+const USAGE: &str = "\
+usage: cfua <command> [arguments]
+
+commands:
+  check FILE...         validate files, printing all errors found
+  get FILE PATH         print value at PATH (e.g. `port`, `server.hosts[0]`)
+  set FILE PATH VALUE   set value at PATH (`key` or `section.key`), keeping
+                        the rest of file untouched; VALUE is written in cfua
+                        syntax, e.g. `8080`, `true`, `[1, 2]` or `\"'text\"`
+  fmt [--check] FILE... reformat files; with --check, only list files
+                        which are not formatted; files with comments which
+                        are not placed before a value or section are refused,
+                        as formatting would remove them
+  keys FILE             list paths of all values
+  to-ini FILE           print file converted into INI
+  from-ini FILE         print INI file converted into cfua
+";
+
+const CONVERSION_NOTE: &str = "
+conversions print every construct which could not be represented,
+and exit with non-zero code if there was any
+";
+
+#[cfg(feature = "json")]
+const JSON_USAGE: &str = "  to-json FILE          print file converted into JSON
+  from-json FILE        print JSON file converted into cfua
+";
+
+#[cfg(feature = "toml")]
+const TOML_USAGE: &str = "  to-toml FILE          print file converted into TOML
+  from-toml FILE        print TOML file converted into cfua
+";
+
+/// Result of a command: `Ok(false)` means that command ran, but its
+/// outcome should be reported with non-zero exit code.
+type CommandResult = Result<bool, String>;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    let result = match args.as_slice() {
+        ["check", files @ ..] if !files.is_empty() => check(files),
+        ["get", file, path] => get(file, path),
+        ["set", file, path, value] => set(file, path, value),
+        ["fmt", "--check", files @ ..] if !files.is_empty() => fmt(files, true),
+        ["fmt", files @ ..] if !files.is_empty() => fmt(files, false),
+        ["keys", file] => keys(file),
+        ["to-ini", file] => load(file).map(|data| report(file, data.to_ini())),
+        ["from-ini", file] => read(file).map(|input| report(file, Cfua::from_ini(&input))),
+        #[cfg(feature = "toml")]
+        ["to-toml", file] => load(file).map(|data| report(file, data.to_toml())),
+        #[cfg(feature = "toml")]
+        ["from-toml", file] => read(file)
+            .and_then(|input| Cfua::from_toml(&input).map_err(|e| format!("{file}: {e}")))
+            .map(|result| report(file, result)),
+        #[cfg(feature = "json")]
+        ["to-json", file] => to_json(file),
+        #[cfg(feature = "json")]
+        ["from-json", file] => from_json(file),
+        ["help" | "-h" | "--help"] => {
+            print!("{}", usage());
+            Ok(true)
+        },
+        _ => {
+            eprint!("{}", usage());
+            return ExitCode::from(2);
+        },
+    };
+
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(message) => {
+            eprintln!("cfua: {message}");
+            ExitCode::FAILURE
+        },
+    }
+}
+
+fn usage() -> String {
+    let mut usage = USAGE.to_string();
+    #[cfg(feature = "json")]
+    usage.push_str(JSON_USAGE);
+    #[cfg(feature = "toml")]
+    usage.push_str(TOML_USAGE);
+    usage.push_str(CONVERSION_NOTE);
+    usage
+}
+
+fn read(file: &str) -> Result<String, String> {
+    fs::read_to_string(file).map_err(|e| format!("{file}: {e}"))
+}
+
+fn load(file: &str) -> Result<Cfua, String> {
+    Cfua::from_file_path(file).map_err(|e| format!("{file}: {e}"))
+}
+
+fn check(files: &[&str]) -> CommandResult {
+    let mut valid = true;
+    for file in files {
+        let (_, errors) = Cfua::from_string_recovering(read(file)?);
+        for error in &errors {
+            eprintln!("{file}: {error}");
+        }
+        valid &= errors.is_empty();
+    }
+    Ok(valid)
+}
+
+fn get(file: &str, path: &str) -> CommandResult {
+    let data = load(file)?;
+    let Some(value) = data.query(path) else {
+        return Err(format!("{file}: no value at '{path}'"));
+    };
+    print!("{}", format_output(value));
+    Ok(true)
+}
+
+/// Formats `value` for printing by `get`. Strings are printed without
+/// quote, so that they can be used directly by scripts, and array
+/// elements are printed one per line.
+fn format_output(value: &CfuaType) -> String {
+    match value {
+        CfuaType::String(value) => format!("{value}\n"),
+        CfuaType::Array(elements) => elements.iter().map(format_output).collect(),
+        CfuaType::Section(section) => section.to_string(),
+        other => format!("{other}\n"),
+    }
+}
+
+fn set(file: &str, path: &str, value: &str) -> CommandResult {
+    let value = parse_value(value)?;
+    let mut document = Document::parse(&read(file)?).map_err(|e| format!("{file}: {e}"))?;
+    document.set(path, value).map_err(|e| e.to_string())?;
+    fs::write(file, document.to_string()).map_err(|e| format!("{file}: {e}"))?;
+    Ok(true)
+}
+
+/// Parses `text` written in cfua syntax into a single value.
+fn parse_value(text: &str) -> Result<CfuaType, String> {
+    let data = match Cfua::from_string(format!("value: {text}\n")) {
+        Ok(data) => data,
+        Err(CfuaError::UnknownKeyword(..)) => {
+            return Err(format!("invalid value '{text}' (strings must start with a quote, e.g. \"'{text}\")"));
+        },
+        Err(_) => return Err(format!("invalid value '{text}'")),
+    };
+    match data.read_value("value") {
+        Some(value) if data.len() == 1 => Ok(value.clone()),
+        _ => Err(format!("invalid value '{text}': expected a single value")),
+    }
+}
+
+fn fmt(files: &[&str], check_only: bool) -> CommandResult {
+    let mut formatted = true;
+    for file in files {
+        let input = read(file)?;
+        let output = Cfua::from_string(&input)
+            .map_err(|e| format!("{file}: {e}"))?
+            .to_string();
+        if comments(&input) != comments(&output) {
+            return Err(format!("{file}: formatting would remove comments which are not placed before a value or section"));
+        }
+        if input == output {
+            continue;
+        }
+        if check_only {
+            println!("{file}");
+            formatted = false;
+        } else {
+            fs::write(file, output).map_err(|e| format!("{file}: {e}"))?;
+        }
+    }
+    Ok(formatted)
+}
+
+/// Returns text of all comment lines in `text`, sorted, as formatting
+/// may move comments along with values they are attached to.
+fn comments(text: &str) -> Vec<&str> {
+    let mut comments: Vec<_> = text.lines()
+        .filter_map(|line| line.strip_prefix('%'))
+        .map(|comment| comment.strip_prefix(' ').unwrap_or(comment).trim_end())
+        .collect();
+    comments.sort_unstable();
+    comments
+}
+
+fn keys(file: &str) -> CommandResult {
+    for path in load(file)?.value_paths() {
+        println!("{path}");
+    }
+    Ok(true)
+}
+
+#[cfg(feature = "json")]
+fn to_json(file: &str) -> CommandResult {
+    let json = load(file)?.to_json().map_err(|e| format!("{file}: {e}"))?;
+    let output = serde_json::to_string_pretty(&json).map_err(|e| format!("{file}: {e}"))?;
+    println!("{output}");
+    Ok(true)
+}
+
+#[cfg(feature = "json")]
+fn from_json(file: &str) -> CommandResult {
+    let json = serde_json::from_str(&read(file)?).map_err(|e| format!("{file}: {e}"))?;
+    print!("{}", Cfua::from_json(&json).map_err(|e| format!("{file}: {e}"))?);
+    Ok(true)
+}
+
+/// Prints `output` of a conversion, along with its `diagnostics`.
+/// Returns `false` if there were any.
+fn report<T>(file: &str, (output, diagnostics): (T, Vec<CfuaError>)) -> bool
+where T: fmt::Display {
+    print!("{output}");
+    for diagnostic in &diagnostics {
+        eprintln!("{file}: {diagnostic}");
+    }
+    diagnostics.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values() {
+        assert_eq!(parse_value("8080"), Ok(CfuaType::Integer(8080)));
+        assert_eq!(parse_value("'text"), Ok(CfuaType::String("text".to_string())));
+        assert_eq!(parse_value("[1, 2]"), Ok(CfuaType::Array(vec![CfuaType::Integer(1), CfuaType::Integer(2)])));
+        assert!(parse_value("text").is_err());
+        assert!(parse_value("1\nother: 2").is_err());
+
+        assert_eq!(format_output(&CfuaType::String("text".to_string())), "text\n");
+        assert_eq!(format_output(&CfuaType::Float(1.0)), "1.0\n");
+        assert!(parse_value("['a]").is_err());
+        assert_eq!(format_output(&parse_value("[1, 2]").unwrap()), "1\n2\n");
+    }
+
+    #[test]
+    fn comment_lines() {
+        assert_eq!(comments("% b\nx: 'a\n%a\n'%c\n%\n"), vec!["", "a", "b"]);
+    }
+
+    #[test]
+    fn paths() {
+        let data = Cfua::from_string("name: 'example\n@server\nport: 80\nhosts: [1]\n").unwrap();
+        let mut paths = data.value_paths();
+        paths.sort();
+        assert_eq!(paths, vec!["name", "server.hosts", "server.port"]);
+    }
+}
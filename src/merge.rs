@@ -0,0 +1,290 @@
+//! Merging of whole [`Cfua`] structures.
+//!
+//! [`Cfua::merge`] merges sections key by key, and resolves values
+//! present in both structures according to [`MergeStrategy`].
+//! [`Layers`](crate::layer::Layers) uses it with [`OnConflict::Replace`]
+//! to merge files and data, so both follow the same rules.
+
+use crate::{cfua::CfuaType, Cfua, CfuaError};
+
+/// What [`Cfua::merge`] does with values which are present
+/// in both structures, but differ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnConflict {
+    /// Value of merged structure replaces existing one.
+    #[default]
+    Replace,
+    /// Existing value is kept.
+    KeepFirst,
+    /// Merge fails with [`CfuaError::MergeConflict`], listing paths
+    /// of all conflicting values. Nothing is merged then.
+    Error,
+}
+
+/// What [`Cfua::merge`] does with arrays present in both structures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArrayMerge {
+    /// Arrays are treated like other values, as described by [`OnConflict`].
+    #[default]
+    Replace,
+    /// Elements of merged array are appended to existing one, unless
+    /// element types differ (which is treated as conflict).
+    Append,
+}
+
+/// Options of [`Cfua::merge`].
+///
+/// ## Example
+///
+/// ```
+/// use cfua::{ArrayMerge, Cfua, MergeStrategy, OnConflict};
+///
+/// let mut data = Cfua::from_string("hosts: [\n#'alpha\n]\n@server\nport: 8080\n").unwrap();
+/// let local = Cfua::from_string("hosts: [\n#'beta\n]\n@server\nport: 9000\n").unwrap();
+///
+/// let strategy = MergeStrategy { on_conflict: OnConflict::Error, arrays: ArrayMerge::Append };
+/// let Err(error) = data.merge(local.clone(), strategy) else { panic!() };
+/// assert_eq!(error.to_string(), "merged values conflict at 'server.port'");
+///
+/// data.merge(local, MergeStrategy { arrays: ArrayMerge::Append, ..Default::default() }).unwrap();
+/// assert_eq!(data.query("server.port").and_then(|v| v.as_integer()), Some(9000));
+/// assert_eq!(data.query("hosts[1]").and_then(|v| v.as_str()), Some("beta"));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MergeStrategy {
+    /// Handling of values which differ.
+    pub on_conflict: OnConflict,
+    /// Handling of arrays.
+    pub arrays: ArrayMerge,
+}
+
+/// Way in which two values with the same key are merged.
+enum Action {
+    /// Existing value is missing, or replaced.
+    Take,
+    /// Values are equal.
+    Keep,
+    Append,
+    MergeSections,
+}
+
+impl Cfua {
+    /// Merges `other` into this structure. Values missing here are
+    /// appended, along with their comments. Sections present in both
+    /// structures are merged key by key, so a section of `other` only
+    /// overrides values it contains. Values which are present in both
+    /// and differ are resolved according to `strategy`. Replaced values
+    /// take comments of `other`, or lose theirs if it has none.
+    ///
+    /// Configs composed of several files can be merged in order from
+    /// the most general to the most specific one, e.g. `base.cfua`,
+    /// `prod.cfua` and `local.cfua`, with default strategy, which
+    /// replaces values.
+    pub fn merge(&mut self, other: Cfua, strategy: MergeStrategy) -> Result<(), CfuaError> {
+        if strategy.on_conflict == OnConflict::Error {
+            let mut conflicts = Vec::new();
+            find_conflicts(self, &other, None, strategy, &mut conflicts);
+            if !conflicts.is_empty() {
+                return Err(CfuaError::MergeConflict(conflicts));
+            }
+        }
+        merge_into(self, other, strategy);
+        Ok(())
+    }
+}
+
+/// Returns how `new` value should be merged with `current` one,
+/// or `None` if they conflict.
+fn action(current: Option<&CfuaType>, new: &CfuaType, strategy: MergeStrategy) -> Option<Action> {
+    match (current, new) {
+        (None, _) => Some(Action::Take),
+        (Some(CfuaType::Section(_)), CfuaType::Section(_)) => Some(Action::MergeSections),
+        (Some(current), new) if same_value(current, new) => Some(Action::Keep),
+        (Some(CfuaType::Array(current)), CfuaType::Array(new))
+            if strategy.arrays == ArrayMerge::Append && same_element_type(current, new) => Some(Action::Append),
+        _ => None,
+    }
+}
+
+/// Compares values like `==`, but floats by their bits, so that NaN
+/// is equal to itself, and merging the same data never conflicts.
+fn same_value(current: &CfuaType, new: &CfuaType) -> bool {
+    match (current, new) {
+        (CfuaType::Float(current), CfuaType::Float(new)) => current.to_bits() == new.to_bits(),
+        (CfuaType::Array(current), CfuaType::Array(new)) => {
+            current.len() == new.len() && current.iter().zip(new).all(|(current, new)| same_value(current, new))
+        },
+        _ => current == new,
+    }
+}
+
+fn same_element_type(current: &[CfuaType], new: &[CfuaType]) -> bool {
+    match (current.first(), new.first()) {
+        (Some(current), Some(new)) => current.has_same_type(new),
+        _ => true,
+    }
+}
+
+/// Collects paths of values of `other` which conflict with `current`.
+fn find_conflicts(current: &Cfua, other: &Cfua, section: Option<&str>, strategy: MergeStrategy, conflicts: &mut Vec<String>) {
+    for (key, value) in other {
+        match action(current.read_value(key), value, strategy) {
+            None => conflicts.push(match section {
+                Some(section) => format!("{section}.{key}"),
+                None => key.to_string(),
+            }),
+            Some(Action::MergeSections) => if let (Some(current), CfuaType::Section(other)) = (current.read_section_ref(key), value) {
+                find_conflicts(current, other, Some(key), strategy, conflicts);
+            },
+            Some(_) => {},
+        }
+    }
+}
+
+/// Moves values of `other` into `target`.
+fn merge_into(target: &mut Cfua, other: Cfua, strategy: MergeStrategy) {
+    for (key, value, comment, radix) in other.into_entries() {
+        match action(target.read_value(&key), &value, strategy) {
+            None if strategy.on_conflict == OnConflict::KeepFirst => {},
+            Some(Action::Keep) => {},
+            Some(Action::Take) | None => {
+                target.replace(key.clone(), value);
+                if let Some(position) = target.position(&key) {
+                    target.set_radix_at(position, radix);
+                    target.set_comment_at(position, comment);
+                }
+            },
+            Some(Action::Append) => {
+                target.entry(&key).and_modify(|current| if let (CfuaType::Array(current), CfuaType::Array(new)) = (current, value) {
+                    current.extend(new);
+                });
+            },
+            Some(Action::MergeSections) => {
+                target.entry(&key).and_modify(|current| if let (CfuaType::Section(current), CfuaType::Section(new)) = (current, value) {
+                    merge_into(current, new, strategy);
+                });
+                if let Some(position) = target.position(&key)
+                    && target.comment_at(position).is_none() {
+                    target.set_comment_at(position, comment);
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CfuaWriter, FormatOptions};
+
+    use super::*;
+
+    const BASE: &str =
+r"name: 'base
+mask: hff
+hosts: [
+#'alpha
+]
+@server
+port: 8080
+workers: 4
+";
+
+    const LOCAL: &str =
+r"% local override
+name: 'local
+mask: b1010
+hosts: [
+#'beta
+]
+debug: true
+@server
+port: 9000
+workers: 4
+@client
+retries: 3
+";
+
+    fn merged(strategy: MergeStrategy) -> Result<Cfua, CfuaError> {
+        let mut data = Cfua::from_string(BASE).unwrap();
+        data.merge(Cfua::from_string(LOCAL).unwrap(), strategy)?;
+        Ok(data)
+    }
+
+    #[test]
+    fn replace() {
+        let data = merged(MergeStrategy::default()).unwrap();
+
+        let expected =
+r"% local override
+name: 'local
+mask: b1010
+hosts: [
+#'beta
+]
+debug: true
+@server
+port: 9000
+workers: 4
+@client
+retries: 3
+";
+        let writer = CfuaWriter::new(FormatOptions { radix: None, ..Default::default() });
+        assert_eq!(writer.format(&data).unwrap(), expected);
+    }
+
+    #[test]
+    fn keep_first() {
+        let data = merged(MergeStrategy { on_conflict: OnConflict::KeepFirst, arrays: ArrayMerge::Append }).unwrap();
+
+        assert_eq!(data.read_string("name"), Some("base".to_string()));
+        assert_eq!(data.read_comment("name"), None);
+        assert_eq!(data.read_integer("mask"), Some(0xff));
+        assert_eq!(data.query("server.port").and_then(CfuaType::as_integer), Some(8080));
+        assert_eq!(data.read_bool("debug"), Some(true));
+        assert_eq!(data.query("client.retries").and_then(CfuaType::as_integer), Some(3));
+        assert_eq!(data.read_array("hosts"), Some(vec![
+            CfuaType::String("alpha".to_string()),
+            CfuaType::String("beta".to_string()),
+        ]));
+    }
+
+    #[test]
+    fn conflicts() {
+        let Err(CfuaError::MergeConflict(keys)) = merged(MergeStrategy { on_conflict: OnConflict::Error, ..Default::default() }) else {
+            panic!()
+        };
+        assert_eq!(keys, vec!["name", "mask", "hosts", "server.port"]);
+
+        // arrays with different element types cannot be appended
+        let mut data = Cfua::from_string("ports: [80]\n").unwrap();
+        let other = Cfua::from_string("ports: [\n#'http\n]\nname: 'example\n").unwrap();
+        let strategy = MergeStrategy { on_conflict: OnConflict::Error, arrays: ArrayMerge::Append };
+        let Err(error) = data.merge(other, strategy) else { panic!() };
+        assert_eq!(error.to_string(), "merged values conflict at 'ports'");
+        assert_eq!(data.read_value("name"), None);
+    }
+
+    #[test]
+    fn equal_floats_do_not_conflict() {
+        let mut data = Cfua::from_string("ratio: nan
+limits: [nan, 1.5]
+zero: 0.0
+").unwrap();
+        let strategy = MergeStrategy { on_conflict: OnConflict::Error, ..Default::default() };
+        data.merge(data.clone(), strategy).unwrap();
+
+        let Err(CfuaError::MergeConflict(keys)) = data.merge(Cfua::from_string("zero: -0.0
+").unwrap(), strategy) else {
+            panic!()
+        };
+        assert_eq!(keys, vec!["zero"]);
+    }
+
+    #[test]
+    fn replaced_values_drop_comments() {
+        let mut data = Cfua::from_string("% default port\nport: 8080\n% default name\nname: 'base\n").unwrap();
+        data.merge(Cfua::from_string("port: 9000\nname: 'base\n").unwrap(), MergeStrategy::default()).unwrap();
+
+        assert_eq!(data.to_string(), "port: 9000\n% default name\nname: 'base\n");
+    }
+}
@@ -0,0 +1,66 @@
+//! Helpers shared by conversions between cfua and other formats,
+//! which report constructs that could not be represented.
+
+use crate::{parser::is_valid_name, CfuaError};
+
+/// Returns diagnostic about value at `path`.
+pub(crate) fn diagnostic<M>(path: &str, message: M) -> CfuaError
+where M: ToString {
+    CfuaError::Conversion { key: path.to_string(), message: message.to_string() }
+}
+
+/// Joins `section` and `key` into path accepted by [`Cfua::query`](crate::Cfua::query).
+pub(crate) fn join(section: Option<&str>, key: &str) -> String {
+    match section {
+        Some(section) => format!("{section}.{key}"),
+        None => key.to_string(),
+    }
+}
+
+/// Converts `name` of a key or section used by another format into cfua
+/// key, by lowercasing it and replacing `_`, `.` and spaces with hyphens.
+/// Renamed keys are reported. Returns `None` (also reported) if `name`
+/// cannot be converted.
+pub(crate) fn import_key(name: &str, section: Option<&str>, diagnostics: &mut Vec<CfuaError>) -> Option<String> {
+    let key: String = name.chars()
+        .map(|char| match char {
+            '_' | '.' | ' ' => '-',
+            char => char.to_ascii_lowercase(),
+        })
+        .collect();
+
+    if !is_valid_name(&key) {
+        diagnostics.push(diagnostic(&join(section, name), "name cannot be converted into cfua key"));
+        None
+    } else {
+        if key != name {
+            diagnostics.push(diagnostic(&join(section, name), format!("key renamed to '{key}'")));
+        }
+        Some(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn import_keys() {
+        let mut diagnostics = Vec::new();
+
+        assert_eq!(import_key("port", None, &mut diagnostics), Some("port".to_string()));
+        assert!(diagnostics.is_empty());
+        assert_eq!(import_key("Max_Connections", Some("server"), &mut diagnostics), Some("max-connections".to_string()));
+        assert_eq!(import_key("tls.cert file", None, &mut diagnostics), Some("tls-cert-file".to_string()));
+        assert_eq!(import_key("ipv4", None, &mut diagnostics), None);
+        assert_eq!(import_key("_private", None, &mut diagnostics), None);
+
+        let messages: Vec<_> = diagnostics.iter().map(ToString::to_string).collect();
+        assert_eq!(messages, vec![
+            "key renamed to 'max-connections' (at 'server.Max_Connections')",
+            "key renamed to 'tls-cert-file' (at 'tls.cert file')",
+            "name cannot be converted into cfua key (at 'ipv4')",
+            "name cannot be converted into cfua key (at '_private')",
+        ]);
+    }
+}
@@ -1,6 +1,6 @@
-use std::{fmt::Display, io};
+use std::{collections::VecDeque, fmt::Display, io::{self, BufRead}, num::IntErrorKind};
 
-use crate::{cfua::CfuaType, Cfua};
+use crate::{cfua::CfuaType, events::Event, write::Radix, Cfua};
 
 #[derive(Debug, PartialEq, Eq)]
 enum State {
@@ -22,150 +22,553 @@ enum ValueType {
     Integer,
     Float,
     String,
+    #[allow(dead_code)]
     Bool,
     Other,
 }
 
+/// Position of a char in parsed input.
+#[derive(Debug, Clone, Copy, Default)]
+struct Cursor {
+    line: usize,
+    column: usize,
+    offset: usize,
+}
+
+/// Returns `true` if `char` may follow `preceding` chars of a key
+/// or section name. Names consist of lowercase letters and hyphens,
+/// and a hyphen may appear only after the first two chars.
+pub(crate) fn is_name_char(char: char, preceding: &str) -> bool {
+    match char {
+        'a'..='z' => true,
+        '-' => preceding.len() > 1,
+        _ => false,
+    }
+}
+
+/// Returns `true` if `name` is a valid key or section name,
+/// which can be read back by parser.
+pub(crate) fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name.char_indices().all(|(i, char)| is_name_char(char, &name[..i]))
+}
+
 /// Structure storing data needed for parser.
+/// 
+/// Input is fed line by line, so only currently read line
+/// and currently read value are kept in memory. Parsed input
+/// is turned into a queue of [`Event`]s, which are either consumed
+/// by [`Events`](crate::Events) iterator or collected into [`Cfua`].
 pub struct ParserData {
-    input: String,
+    /// currently read line, used for error messages
+    line: String,
     key_buffer: String,
     value_buffer: String,
     value_type: ValueType,
-    array_buffer: Vec<CfuaType>,
+    /// number of elements pushed into currently read array
+    array_len: usize,
+    /// type of first element of currently read array
+    array_type: Option<ValueType>,
+    comment_buffer: String,
+    /// comments read after a multiline string, which are queued once
+    /// it is known whether they are placed inside or after the string
+    held_comments: Vec<String>,
     state: State,
+    /// queued events, with radix of integer literals
+    events: VecDeque<(Event, Radix)>,
+    cursor: Cursor,
+    value_start: Cursor,
+}
+
+/// Collects parser events into [`Cfua`] structure.
+struct Builder {
     data: Cfua,
+    /// section which is currently being read, with its name and comment
+    section: Option<(String, Cfua, Option<String>)>,
+    key: String,
+    /// comment attached to last read key
+    key_comment: Option<String>,
+    array: Vec<CfuaType>,
+    /// radix of first element of currently read array
+    array_radix: Radix,
+    /// comment lines read since last key or section
+    comment: Option<String>,
+}
+
+/// Location in parsed input at which an error occurred.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    /// Line number, starting from 1.
+    pub line: usize,
+    /// Column number (counted in chars), starting from 1.
+    pub column: usize,
+    /// Byte offset from the beginning of input.
+    pub offset: usize,
+    /// Content of the line containing the error, without line terminator.
+    pub source_line: String,
 }
 
 /// Errors which may occur while parsing data.
+///
+/// Every error produced by parser carries a [`Span`] pointing
+/// at offending char or value. When displayed, the error is followed
+/// by an annotated snippet of the line it occurred in.
+///
+/// The enum is non-exhaustive, as some variants (like `Serde`)
+/// are available only with cargo features.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum CfuaError {
-    EmptyValue,
-    NonGraphicChar,
-    InvalidChar,
-    InvalidKeyChar(char),
-    InvalidHyphenInKey,
-    InvalidSectionChar(char),
-    InvalidHyphenInSection,
-    UnknownKeyword(String),
-    NestedArray,
-    MixedArrayType,
-    MixedArrayDecl,
-    StringInSimpleArray,
-    InvalidArrayValue(String),
+    EmptyValue(Span),
+    NonGraphicChar(Span),
+    InvalidChar(Span),
+    InvalidKeyChar(char, Span),
+    InvalidHyphenInKey(Span),
+    InvalidSectionChar(char, Span),
+    InvalidHyphenInSection(Span),
+    UnknownKeyword(String, Span),
+    NestedArray(Span),
+    /// Array element has different type than the first element.
+    MixedArrayType(Span),
+    MixedArrayDecl(Span),
+    StringInSimpleArray(Span),
+    InvalidArrayValue(String, Span),
+    /// Integer literal does not fit in 64-bit signed integer.
+    IntegerOverflow(String, Span),
+    /// Radix prefix (`b`, `h` or `o`) is not followed by any digits.
+    EmptyRadixLiteral(String, Span),
+    /// Integer literal is not a valid number, e.g. `12a` or `-`.
+    MalformedInteger(String, Span),
+    /// Float literal is not written in plain decimal notation,
+    /// e.g. `1.2.3` or `1e5`.
+    MalformedFloat(String, Span),
+    /// Input ends before array is closed with `]`.
+    UnclosedArray(Span),
     IoError(io::Error),
+    /// Section cannot be written into other section, as cfua syntax
+    /// does not support nesting.
+    NestedSection(String),
+    /// No value is stored with given key.
+    KeyNotFound(String),
+    /// Value stored with given key has different type than requested.
+    TypeMismatch { key: String, expected: &'static str, found: &'static str },
+    /// Value with given key already exists.
+    DuplicateKey(String),
+    /// Key or section name cannot be written, as it would be rejected
+    /// by parser.
+    InvalidKey(String),
+    /// Value stored with given key does not fit in requested type.
+    OutOfRange { key: String, value: String, target: &'static str },
+    /// Error reported while converting between cfua data and Rust types
+    /// with serde. `key` contains path to a value which failed, in form
+    /// accepted by [`Cfua::query`], or is empty for top-level errors.
+    #[cfg(feature = "serde")]
+    Serde { key: String, message: String },
+    /// Value which cannot be converted into cfua value, or between cfua
+    /// and another format such as JSON, TOML or INI, or written as cfua
+    /// text, such as an empty array. `key` contains path to the value,
+    /// in form accepted by [`Cfua::query`], or is empty if error is not
+    /// related to a value.
+    Conversion { key: String, message: String },
+    /// Text given by an environment variable or command-line argument
+    /// cannot be converted into type of the value it overrides.
+    /// `source` describes where the text came from.
+    InvalidOverride { key: String, value: String, expected: &'static str, source: String },
+    /// Environment variable with given name matches more than one value,
+    /// e.g. `APP_A_B_C` matches both `a.b-c` and `a-b.c`. `paths` holds
+    /// paths of all matching values.
+    AmbiguousVariable { name: String, paths: Vec<String> },
+    /// Values with given paths are different in both structures merged
+    /// with [`OnConflict::Error`](crate::OnConflict::Error).
+    MergeConflict(Vec<String>),
+}
+
+impl CfuaError {
+    /// Returns location of the error in parsed input,
+    /// or `None` if error is not related to input content.
+    pub fn span(&self) -> Option<&Span> {
+        match self {
+            CfuaError::EmptyValue(span) |
+            CfuaError::NonGraphicChar(span) |
+            CfuaError::InvalidChar(span) |
+            CfuaError::InvalidKeyChar(_, span) |
+            CfuaError::InvalidHyphenInKey(span) |
+            CfuaError::InvalidSectionChar(_, span) |
+            CfuaError::InvalidHyphenInSection(span) |
+            CfuaError::UnknownKeyword(_, span) |
+            CfuaError::NestedArray(span) |
+            CfuaError::MixedArrayType(span) |
+            CfuaError::MixedArrayDecl(span) |
+            CfuaError::StringInSimpleArray(span) |
+            CfuaError::InvalidArrayValue(_, span) |
+            CfuaError::IntegerOverflow(_, span) |
+            CfuaError::EmptyRadixLiteral(_, span) |
+            CfuaError::MalformedInteger(_, span) |
+            CfuaError::MalformedFloat(_, span) |
+            CfuaError::UnclosedArray(span) => Some(span),
+            CfuaError::IoError(_) |
+            CfuaError::NestedSection(_) |
+            CfuaError::KeyNotFound(_) |
+            CfuaError::DuplicateKey(_) |
+            CfuaError::InvalidKey(_) |
+            CfuaError::TypeMismatch { .. } |
+            CfuaError::OutOfRange { .. } |
+            CfuaError::Conversion { .. } |
+            CfuaError::InvalidOverride { .. } |
+            CfuaError::AmbiguousVariable { .. } |
+            CfuaError::MergeConflict(_) => None,
+            #[cfg(feature = "serde")]
+            CfuaError::Serde { .. } => None,
+        }
+    }
+
+    /// Prepends `key` to path of value which failed, for errors
+    /// reported while converting values.
+    pub(crate) fn at_key(self, key: &str) -> Self {
+        let join = |inner: String| if inner.is_empty() {
+            key.to_string()
+        } else if inner.starts_with('[') {
+            format!("{key}{inner}")
+        } else {
+            format!("{key}.{inner}")
+        };
+
+        match self {
+            CfuaError::KeyNotFound(inner) => CfuaError::KeyNotFound(join(inner)),
+            CfuaError::TypeMismatch { key, expected, found } => CfuaError::TypeMismatch { key: join(key), expected, found },
+            CfuaError::OutOfRange { key, value, target } => CfuaError::OutOfRange { key: join(key), value, target },
+            #[cfg(feature = "serde")]
+            CfuaError::Serde { key, message } => CfuaError::Serde { key: join(key), message },
+            CfuaError::Conversion { key, message } => CfuaError::Conversion { key: join(key), message },
+            other => other,
+        }
+    }
 }
 
 impl Display for CfuaError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            CfuaError::EmptyValue => write!(f, "value must not be empty"),
-            CfuaError::NonGraphicChar => write!(f, "non-garphic char"),
-            CfuaError::InvalidChar => write!(f, "invalid syntax"),
-            CfuaError::InvalidKeyChar(ch) => write!(f, "invalid char: '{ch}' in key name"),
-            CfuaError::InvalidHyphenInKey => write!(f, "hyphen not allowed at the beginning of key name"),
-            CfuaError::InvalidSectionChar(ch) => write!(f, "invalid char: '{ch}' in section name"),
-            CfuaError::InvalidHyphenInSection => write!(f, "hyphen not allowed at the beginning of section name"),
-            CfuaError::UnknownKeyword(kw) => write!(f, "unknown keyword: '{kw}'"),
-            CfuaError::NestedArray => write!(f, "nested arrays are not allowed"),
-            CfuaError::MixedArrayType => write!(f, "array type is ambiguous"),
-            CfuaError::MixedArrayDecl => write!(f, "mixed comma-based and hash-based array declatation"),
-            CfuaError::StringInSimpleArray => write!(f, "string value in simple array declaration"),
-            CfuaError::InvalidArrayValue(kw) => write!(f, "invalid array element: '{kw}'"),
+            CfuaError::EmptyValue(_) => write!(f, "value must not be empty"),
+            CfuaError::NonGraphicChar(_) => write!(f, "non-garphic char"),
+            CfuaError::InvalidChar(_) => write!(f, "invalid syntax"),
+            CfuaError::InvalidKeyChar(ch, _) => write!(f, "invalid char: '{ch}' in key name"),
+            CfuaError::InvalidHyphenInKey(_) => write!(f, "hyphen not allowed at the beginning of key name"),
+            CfuaError::InvalidSectionChar(ch, _) => write!(f, "invalid char: '{ch}' in section name"),
+            CfuaError::InvalidHyphenInSection(_) => write!(f, "hyphen not allowed at the beginning of section name"),
+            CfuaError::UnknownKeyword(kw, _) => write!(f, "unknown keyword: '{kw}'"),
+            CfuaError::NestedArray(_) => write!(f, "nested arrays are not allowed"),
+            CfuaError::MixedArrayType(_) => write!(f, "array type is ambiguous"),
+            CfuaError::MixedArrayDecl(_) => write!(f, "mixed comma-based and hash-based array declatation"),
+            CfuaError::StringInSimpleArray(_) => write!(f, "string value in simple array declaration"),
+            CfuaError::InvalidArrayValue(kw, _) => write!(f, "invalid array element: '{kw}'"),
+            CfuaError::IntegerOverflow(lit, _) => write!(f, "integer literal '{lit}' is out of range"),
+            CfuaError::EmptyRadixLiteral(lit, _) => write!(f, "missing digits after radix prefix in '{lit}'"),
+            CfuaError::MalformedInteger(lit, _) => write!(f, "malformed integer literal: '{lit}'"),
+            CfuaError::MalformedFloat(lit, _) => write!(f, "malformed float literal: '{lit}'"),
+            CfuaError::UnclosedArray(_) => write!(f, "array is not closed with ']'"),
             CfuaError::IoError(err) => write!(f, "io error: {err}"),
+            CfuaError::NestedSection(name) => write!(f, "section '{name}' cannot be nested in other section"),
+            CfuaError::KeyNotFound(key) => write!(f, "key '{key}' not found"),
+            CfuaError::DuplicateKey(key) => write!(f, "key '{key}' already exists"),
+            CfuaError::InvalidKey(key) => write!(f, "invalid key or section name: '{key}' (only lowercase letters and hyphens are allowed, and neither of the first two chars may be a hyphen)"),
+            CfuaError::TypeMismatch { key, expected, found } => if key.is_empty() {
+                write!(f, "expected {expected}, found {found}")
+            } else {
+                write!(f, "expected {expected} for key '{key}', found {found}")
+            },
+            CfuaError::OutOfRange { key, value, target } => if key.is_empty() {
+                write!(f, "value {value} is out of range for {target}")
+            } else {
+                write!(f, "value {value} for key '{key}' is out of range for {target}")
+            },
+            #[cfg(feature = "serde")]
+            CfuaError::Serde { key, message } => if key.is_empty() {
+                write!(f, "{message}")
+            } else {
+                write!(f, "{message} (at '{key}')")
+            },
+            CfuaError::Conversion { key, message } => if key.is_empty() {
+                write!(f, "{message}")
+            } else {
+                write!(f, "{message} (at '{key}')")
+            },
+            CfuaError::InvalidOverride { key, value, expected, source } => {
+                write!(f, "cannot override '{key}' with '{value}' from {source}: expected {expected}")
+            },
+            CfuaError::AmbiguousVariable { name, paths } => {
+                write!(f, "environment variable {name} matches more than one value: '{}'", paths.join("', '"))
+            },
+            CfuaError::MergeConflict(keys) => {
+                let keys: Vec<_> = keys.iter().map(|key| format!("'{key}'")).collect();
+                write!(f, "merged values conflict at {}", keys.join(", "))
+            },
+        }?;
+
+        if let Some(span) = self.span() {
+            // prints snippet looking like:
+            //  --> 2:5
+            //   |
+            // 2 | port number: 1
+            //   |     ^
+            let gutter = span.line.to_string().len();
+            let padding: String = span.source_line.chars()
+                .take(span.column - 1)
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            write!(f, "\n{:gutter$}--> {}:{}", "", span.line, span.column)?;
+            write!(f, "\n{:gutter$} |", "")?;
+            write!(f, "\n{} | {}", span.line, span.source_line)?;
+            write!(f, "\n{:gutter$} | {padding}^", "")?;
         }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for CfuaError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CfuaError::IoError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl Default for ParserData {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl ParserData {
-    /// Creates empty `ParserData` structure from `input` string.
-    pub fn new(input: String) -> Self {
+    /// Creates empty `ParserData` structure.
+    pub fn new() -> Self {
         Self {
-            input,
+            line: String::with_capacity(256),
             key_buffer: String::with_capacity(256),
             value_buffer: String::with_capacity(256),
             value_type: ValueType::Number,
-            array_buffer: Vec::with_capacity(64),
+            array_len: 0,
+            array_type: None,
+            comment_buffer: String::new(),
+            held_comments: Vec::new(),
             state: State::Reading,
-            data: Cfua::create(),
+            events: VecDeque::new(),
+            cursor: Cursor { line: 1, column: 1, ..Default::default() },
+            value_start: Cursor::default(),
         }
     }
 
-    fn push_value(&mut self) -> Result<(), CfuaError> {
-        if self.value_type == ValueType::String {
-            self.data.write_string(self.key_buffer.clone(), self.value_buffer.strip_prefix('\'').unwrap().to_string());
-        } else if self.value_type == ValueType::Number {
-            if self.value_buffer.contains(".") {
-                self.data.write_float(self.key_buffer.clone(), self.value_buffer.clone().parse().unwrap());
-            } else {
-                // TODO: refactor repeating code
-                if self.value_buffer.starts_with('-') {
-                    if self.value_buffer == "-inf" {
-                        self.data.write_float(self.key_buffer.clone(), f64::NEG_INFINITY);
-                    } else if let Some(c) = self.value_buffer.chars().nth(1) {
-                        match c {
-                            'b' |
-                            'h' |
-                            'o' => self.data.write_integer(self.key_buffer.clone(), i64::from_str_radix(
-                                &self.value_buffer.replace(c, ""),
-                                if c == 'b' { 2 } else if c == 'h' { 16 } else { 8 }).unwrap()
-                            ),
-                            '0'..'9' => self.data.write_integer(self.key_buffer.clone(), self.value_buffer.clone().parse().unwrap()),
-                            x => return Err(CfuaError::UnknownKeyword(x.to_string()))
-                        }
-                    }
-                } else {
-                    if let Some(c) = self.value_buffer.chars().nth(0) {
-                        match c {
-                            'b' |
-                            'h' |
-                            'o' => self.data.write_integer(self.key_buffer.clone(), i64::from_str_radix(
-                                &self.value_buffer.replace(c, ""),
-                                if c == 'b' { 2 } else if c == 'h' { 16 } else { 8 }).unwrap()
-                            ),
-                            '0'..'9' => self.data.write_integer(self.key_buffer.clone(), self.value_buffer.clone().parse().unwrap()),
-                            _ => return Err(CfuaError::UnknownKeyword(self.value_buffer.clone()))
-                        }
-                    } else {
-                        self.data.write_integer(self.key_buffer.clone(), self.value_buffer.clone().parse().unwrap());
-                    }
-                }
-            }
+    /// Creates span pointing at given position in input. Only currently
+    /// read line is known, so position in any previous line is replaced
+    /// with position of currently read char.
+    fn span_at(&self, cursor: Cursor) -> Span {
+        let cursor = if cursor.line == self.cursor.line { cursor } else { self.cursor };
+        let line = self.line.strip_suffix('\n').unwrap_or(&self.line);
+        let line = line.strip_suffix('\r').unwrap_or(line);
+
+        Span {
+            line: cursor.line,
+            column: cursor.column,
+            offset: cursor.offset,
+            source_line: line.to_string(),
+        }
+    }
+
+    /// Creates span pointing at currently read char.
+    fn span(&self) -> Span {
+        self.span_at(self.cursor)
+    }
+
+    /// Creates span pointing at the beginning of currently read value.
+    fn value_span(&self) -> Span {
+        self.span_at(self.value_start)
+    }
+
+    /// Moves cursor past `char`.
+    fn advance(&mut self, char: char) {
+        self.cursor.offset += char.len_utf8();
+        if char == '\n' {
+            self.cursor.line += 1;
+            self.cursor.column = 1;
         } else {
-            if self.value_buffer == "true" {
-                self.data.write_bool(self.key_buffer.clone(), true);
-            } else if self.value_buffer == "false" {
-                self.data.write_bool(self.key_buffer.clone(), false);
-            } else if self.value_buffer == "nan" {
-                self.data.write_float(self.key_buffer.clone(), f64::NAN);
-            } else if self.value_buffer == "inf" {
-                self.data.write_float(self.key_buffer.clone(), f64::INFINITY);
-            } else {
-                return Err(CfuaError::UnknownKeyword(self.value_buffer.clone()));
+            self.cursor.column += 1;
+        }
+    }
+
+    /// Parses numeric value stored in value buffer. Returns error
+    /// instead of panicking on malformed or out of range literals.
+    fn parse_number(&self) -> Result<CfuaType, CfuaError> {
+        let literal = self.value_buffer.trim_end_matches(' ');
+        let (negative, unsigned) = match literal.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, literal),
+        };
+
+        match (unsigned, negative) {
+            ("inf", true) => return Ok(CfuaType::Float(f64::NEG_INFINITY)),
+            // reached only in arrays, other values are parsed as keywords
+            ("inf", false) => return Ok(CfuaType::Float(f64::INFINITY)),
+            ("nan", false) => return Ok(CfuaType::Float(f64::NAN)),
+            _ => {},
+        }
+
+        if unsigned.contains('.') {
+            // only plain decimal notation is allowed, so e.g. `1e5`
+            // or `1.2.3` are rejected even if Rust could parse them
+            let dots = unsigned.matches('.').count();
+            if dots > 1 || !unsigned.chars().all(|c| c == '.' || c.is_ascii_digit()) || unsigned.len() == 1 {
+                return Err(CfuaError::MalformedFloat(literal.to_string(), self.value_span()));
             }
+            return match literal.parse() {
+                Ok(f) => Ok(CfuaType::Float(f)),
+                Err(_) => Err(CfuaError::MalformedFloat(literal.to_string(), self.value_span())),
+            };
         }
 
-        self.key_buffer.clear();
+        let (radix, digits) = match unsigned.chars().next() {
+            Some('b') => (2, &unsigned[1..]),
+            Some('h') => (16, &unsigned[1..]),
+            Some('o') => (8, &unsigned[1..]),
+            Some('0'..='9') => (10, unsigned),
+            Some(_) => return Err(CfuaError::UnknownKeyword(literal.to_string(), self.value_span())),
+            None => return Err(CfuaError::MalformedInteger(literal.to_string(), self.value_span())),
+        };
+
+        if digits.is_empty() {
+            return Err(CfuaError::EmptyRadixLiteral(literal.to_string(), self.value_span()));
+        }
+        // `from_str_radix` accepts sign, which is not allowed after prefix
+        if digits.starts_with(['+', '-']) {
+            return Err(CfuaError::MalformedInteger(literal.to_string(), self.value_span()));
+        }
+
+        let signed = if negative { format!("-{digits}") } else { digits.to_string() };
+        match i64::from_str_radix(&signed, radix) {
+            Ok(i) => Ok(CfuaType::Integer(i)),
+            Err(e) => match e.kind() {
+                IntErrorKind::PosOverflow |
+                IntErrorKind::NegOverflow => self.parse_big_integer(&signed, radix),
+                _ => Err(CfuaError::MalformedInteger(literal.to_string(), self.value_span())),
+            },
+        }
+    }
+
+    /// Parses integer literal which doesn't fit in `i64`, picking the
+    /// smallest fitting representation.
+    fn parse_big_integer(&self, signed: &str, radix: u32) -> Result<CfuaType, CfuaError> {
+        if let Ok(u) = u64::from_str_radix(signed, radix) {
+            return Ok(CfuaType::Unsigned(u));
+        }
+        #[cfg(feature = "bigint")]
+        if let Some(i) = num_bigint::BigInt::parse_bytes(signed.as_bytes(), radix) {
+            return Ok(CfuaType::BigInt(i));
+        }
+
+        let literal = self.value_buffer.trim_end_matches(' ');
+        Err(CfuaError::IntegerOverflow(literal.to_string(), self.value_span()))
+    }
+
+    /// Queues `event` for consumer. Must be called before value buffer
+    /// is cleared, so that radix of integer literal can be recorded.
+    fn emit(&mut self, event: Event) {
+        let radix = match &event {
+            Event::Value(value) |
+            Event::ArrayElement(value) if value.is_integer() => {
+                let literal = self.value_buffer.trim_start_matches('-');
+                match literal.chars().next() {
+                    Some('b') => Radix::Binary,
+                    Some('o') => Radix::Octal,
+                    Some('h') => Radix::Hexadecimal,
+                    _ => Radix::Decimal,
+                }
+            },
+            _ => Radix::Decimal,
+        };
+        self.events.push_back((event, radix));
+    }
+
+    /// Returns `true` if parser is between values, i.e. next line
+    /// starts a new key, section or comment, or continues a string.
+    pub(crate) fn is_reading(&self) -> bool {
+        self.state == State::Reading
+    }
+
+    /// Takes next queued event.
+    pub(crate) fn next_event(&mut self) -> Option<Event> {
+        self.events.pop_front().map(|(event, _)| event)
+    }
+
+    /// Takes next queued event, along with radix of integer literal
+    /// (which is decimal for other events).
+    fn next_event_with_radix(&mut self) -> Option<(Event, Radix)> {
+        self.events.pop_front()
+    }
+
+    /// Ends currently read array.
+    fn end_array(&mut self) {
+        self.emit(Event::ArrayEnd);
+        self.array_len = 0;
+        self.array_type = None;
+        self.state = State::Reading;
+    }
+
+    fn push_value(&mut self) -> Result<(), CfuaError> {
+        let value = if self.value_type == ValueType::String {
+            CfuaType::String(self.value_buffer.strip_prefix('\'').unwrap().to_string())
+        } else if self.value_type == ValueType::Number {
+            self.parse_number()?
+        } else if self.value_buffer == "true" {
+            CfuaType::Bool(true)
+        } else if self.value_buffer == "false" {
+            CfuaType::Bool(false)
+        } else if self.value_buffer == "nan" {
+            CfuaType::Float(f64::NAN)
+        } else if self.value_buffer == "inf" {
+            CfuaType::Float(f64::INFINITY)
+        } else {
+            return Err(CfuaError::UnknownKeyword(self.value_buffer.clone(), self.value_span()));
+        };
+
+        self.emit(Event::Value(value));
         self.value_buffer.clear();
         self.state = State::Reading;
+        self.release_comments();
         Ok(())
     }
 
+    /// Returns `true` if a string value has been read, but it could
+    /// still be continued in following lines.
+    fn has_pending_string(&self) -> bool {
+        self.state == State::Reading && self.value_type == ValueType::String && !self.value_buffer.is_empty()
+    }
+
+    /// Queues comments held while a multiline string was pending.
+    fn release_comments(&mut self) {
+        for text in std::mem::take(&mut self.held_comments) {
+            self.emit(Event::Comment(text));
+        }
+    }
+
+    /// Pushes string value, which could be continued in following lines
+    /// and so is pushed only after reaching next key or section.
+    fn push_pending_string(&mut self) -> Result<(), CfuaError> {
+        if self.has_pending_string() {
+            self.push_value()
+        } else {
+            Ok(())
+        }
+    }
+
     fn section_char(&mut self, char: char) -> Result<(), CfuaError> {
         match char {
             '\n' => {
                 self.state = State::Reading;
-                self.data.write_section(self.key_buffer.clone());
+                let name = std::mem::take(&mut self.key_buffer);
+                self.emit(Event::SectionStart(name));
             },
-            'a'..'z' => self.key_buffer.push(char),
-            '-' => {
-                if self.key_buffer.len() > 1 {
-                    self.key_buffer.push(char);
-                } else {
-                    return Err(CfuaError::InvalidHyphenInSection);
-                }
-            },
-            _ => return Err(CfuaError::InvalidSectionChar(char)),
+            _ if is_name_char(char, &self.key_buffer) => self.key_buffer.push(char),
+            '-' => return Err(CfuaError::InvalidHyphenInSection(self.span())),
+            _ => return Err(CfuaError::InvalidSectionChar(char, self.span())),
         }
 
         Ok(())
@@ -175,17 +578,15 @@ impl ParserData {
         match char {
             ':' => {
                 self.state = State::Separator;
+                let key = std::mem::take(&mut self.key_buffer);
+                self.emit(Event::Key(key));
             },
-            'a'..'z' => self.key_buffer.push(char),
-            '-' => if self.key_buffer.len() > 1 {
-                self.key_buffer.push(char);
-            } else {
-                return Err(CfuaError::InvalidHyphenInKey);
-            },
+            _ if is_name_char(char, &self.key_buffer) => self.key_buffer.push(char),
+            '-' => return Err(CfuaError::InvalidHyphenInKey(self.span())),
             ' ' => if !self.key_buffer.is_empty() {
-                return Err(CfuaError::InvalidKeyChar(char));
+                return Err(CfuaError::InvalidKeyChar(char, self.span()));
             },
-            _ => return Err(CfuaError::InvalidKeyChar(char)),
+            _ => return Err(CfuaError::InvalidKeyChar(char, self.span())),
         }
         
         Ok(())
@@ -196,28 +597,30 @@ impl ParserData {
             Ok(())
         } else if char.is_ascii_graphic() {
             self.state = State::Value;
-            return self.value_char(char);
+            self.value_char(char)
         } else {
-            Err(CfuaError::NonGraphicChar)
+            Err(CfuaError::NonGraphicChar(self.span()))
         }
     }
 
     fn value_char(&mut self, char: char) -> Result<(), CfuaError> {
         // try to guess value type (when buffer is empty)
-        if self.value_buffer.len() == 0 {
+        if self.value_buffer.is_empty() {
+            self.value_start = self.cursor;
             match char {
                 '\'' => self.value_type = ValueType::String,
                 '-' |
                 'b' |
                 'h' |
                 'o' |
-                '0'..'9' => self.value_type = ValueType::Number,
+                '0'..='9' => self.value_type = ValueType::Number,
                 '[' => {
                     self.value_type = ValueType::Other;
                     self.state = State::ArraySimple;
+                    self.emit(Event::ArrayStart);
                     return Ok(());
                 },
-                '\n' => return Err(CfuaError::EmptyValue),
+                '\n' => return Err(CfuaError::EmptyValue(self.span())),
                 _ => self.value_type = ValueType::Other,
             }
         // else, push consecutive chars until newline is approached
@@ -237,27 +640,38 @@ impl ParserData {
     }
 
     fn array_push_value(&mut self) -> Result<(), CfuaError> {
-        match self.value_type {
-            ValueType::Number => if self.value_buffer.contains('.') {
-                self.array_buffer.push(CfuaType::Float(self.value_buffer.parse().unwrap()));
-                self.value_type = ValueType::Float;
-            } else {
-                self.array_buffer.push(CfuaType::Integer(self.value_buffer.parse().unwrap()));
-                self.value_type = ValueType::Integer;
-            },
-            ValueType::Integer => self.array_buffer.push(CfuaType::Integer(self.value_buffer.parse().unwrap())),
-            ValueType::Float => self.array_buffer.push(CfuaType::Float(self.value_buffer.parse().unwrap())),
-            ValueType::String => self.array_buffer.push(CfuaType::String(self.value_buffer.clone())),
+        let value = match self.value_type {
+            ValueType::Number |
+            ValueType::Integer |
+            ValueType::Float => self.parse_number()?,
+            ValueType::String => CfuaType::String(self.value_buffer.clone()),
             ValueType::Bool => if self.value_buffer == "true" {
-                self.array_buffer.push(CfuaType::Bool(true));
+                CfuaType::Bool(true)
             } else if self.value_buffer == "false" {
-                self.array_buffer.push(CfuaType::Bool(false));
+                CfuaType::Bool(false)
             } else {
-                return Err(CfuaError::UnknownKeyword(self.value_buffer.clone()));
+                return Err(CfuaError::UnknownKeyword(self.value_buffer.clone(), self.value_span()));
+            },
+            ValueType::Other => return Err(CfuaError::InvalidArrayValue(self.value_buffer.clone(), self.value_span())),
+        };
+
+        // all elements must have the same type as the first one
+        let value_type = match value {
+            CfuaType::Float(_) => ValueType::Float,
+            CfuaType::String(_) => ValueType::String,
+            CfuaType::Bool(_) => ValueType::Bool,
+            _ => ValueType::Integer,
+        };
+        match &self.array_type {
+            Some(array_type) if *array_type != value_type => {
+                return Err(CfuaError::MixedArrayType(self.value_span()));
             },
-            ValueType::Other => return Err(CfuaError::InvalidArrayValue(self.value_buffer.clone())),
+            Some(_) => {},
+            None => self.array_type = Some(value_type),
         }
-        
+        self.emit(Event::ArrayElement(value));
+
+        self.array_len += 1;
         self.value_buffer.clear();
         if self.state == State::ArrayNormal(Some(true)) {
             self.state = State::ArrayNormal(None);
@@ -266,21 +680,25 @@ impl ParserData {
     }
 
     fn array_char(&mut self, char: char) -> Result<(), CfuaError> {
-        if self.value_buffer.len() == 0 {
+        // `State::ArrayNormal(Some(true))` with empty buffer follows
+        // an empty string element, e.g. `#'`
+        if self.value_buffer.is_empty() && self.state != State::ArrayNormal(Some(true)) {
             // `State::ArrayNormal(Some(false)` is set when there is
             // string element being pushed
             if self.state != State::ArrayNormal(Some(false)) {
                 match char {
                     ' ' |
                     '\n' => return Ok(()),
-                    '#' => if self.array_buffer.len() == 0 {
+                    '#' => if self.array_len == 0 {
                         self.state = State::ArrayNormal(None);
-                    } else {
-                        if self.state == State::ArraySimple {
-                            return Err(CfuaError::MixedArrayDecl);
-                        }
+                    } else if self.state == State::ArraySimple {
+                        return Err(CfuaError::MixedArrayDecl(self.span()));
+                    },
+                    '\'' if self.state == State::ArraySimple => {
+                        return Err(CfuaError::StringInSimpleArray(self.span()));
                     },
                     '\'' => {
+                        self.value_start = self.cursor;
                         self.state = State::ArrayNormal(Some(false));
                         self.value_type = ValueType::String;
                     },
@@ -288,18 +706,23 @@ impl ParserData {
                     'b' |
                     'h' |
                     'o' |
-                    '0'..'9' => {
+                    'i' |
+                    'n' |
+                    '0'..='9' => {
+                        self.value_start = self.cursor;
                         self.value_type = ValueType::Number;
                         self.value_buffer.push(char);
                     },
                     '[' => {
-                        return Err(CfuaError::NestedArray);
+                        return Err(CfuaError::NestedArray(self.span()));
                     },
-                    _ => return Err(CfuaError::InvalidChar),
+                    _ => return Err(CfuaError::InvalidChar(self.span())),
                 }
             // create string value
-            } else {
-                if self.value_type == ValueType::String {
+            } else if self.value_type == ValueType::String {
+                if char == '\n' {
+                    self.state = State::ArrayNormal(Some(true));
+                } else {
                     self.value_buffer.push(char);
                 }
             }
@@ -311,12 +734,8 @@ impl ParserData {
                 State::ArraySimple => if char == ',' {
                     return self.array_push_value();
                 } else if char == ']' {
-                    let result = self.array_push_value();
-                    self.data.write_array(self.key_buffer.clone(), self.array_buffer.clone());
-                    self.key_buffer.clear();
-                    self.array_buffer.clear();
-                    self.state = State::Reading;
-                    return result;
+                    self.array_push_value()?;
+                    self.end_array();
                 } else {
                     self.value_buffer.push(char);
                 },
@@ -333,18 +752,15 @@ impl ParserData {
                         self.state = State::ArrayNormal(Some(false));
                     },
                     ']' => {
-                        let result = self.array_push_value();
-                        self.data.write_array(self.key_buffer.clone(), self.array_buffer.clone());
-                        self.key_buffer.clear();
-                        self.array_buffer.clear();
-                        self.state = State::Reading;
-                        return result;
+                        self.array_push_value()?;
+                        self.end_array();
                     },
                     '#' => return self.array_push_value(),
                     ' ' => {},
-                    _ => return Err(CfuaError::InvalidChar),
+                    _ => return Err(CfuaError::InvalidChar(self.span())),
                 },
-                _ => unreachable!(),
+                // only strings may follow `#`
+                _ => return Err(CfuaError::InvalidArrayValue(self.value_buffer.clone(), self.value_span())),
             }            
         }
 
@@ -354,33 +770,47 @@ impl ParserData {
     fn comment_char(&mut self, char: char) -> Result<(), CfuaError> {
         if char == '\n' {
             self.state = State::Reading;
+            let text = std::mem::take(&mut self.comment_buffer);
+            let text = text.strip_suffix('\r').map(str::to_string).unwrap_or(text);
+            // comment following a multiline string may be placed either
+            // between its lines or after it, so it's queued only once
+            // the string is continued or finished
+            if self.has_pending_string() {
+                self.held_comments.push(text);
+            } else {
+                self.emit(Event::Comment(text));
+            }
+        } else {
+            self.comment_buffer.push(char);
         }
 
         Ok(())
     }
 
+    // chars accepted here which begin a key, section or comment
+    // must be kept in sync with `starts_entry`
     fn basic_char(&mut self, char: char) -> Result<(), CfuaError> {
         match char {
             '%' => self.state = State::Comment,
-            '@' => self.state = State::SectionName,
-            'a'..'z' => {
+            '@' => {
+                self.push_pending_string()?;
+                self.state = State::SectionName;
+            },
+            'a'..='z' => {
                 // reached after newline -- it means that value is not
                 // continued and new key begins
-                let result = if self.value_type == ValueType::String && self.value_buffer.len() > 1 {
-                    self.push_value()
-                } else { Ok(()) };
-
+                self.push_pending_string()?;
                 self.key_buffer.push(char);
                 self.state = State::Key;
-                return result;
             },
             // if there is an `'` at newline, then there is multiline string
-            '\'' => if self.value_buffer.len() > 0 {
+            '\'' => if !self.value_buffer.is_empty() {
+                self.release_comments();
                 self.value_buffer.push('\n');
                 self.state = State::Value;
             },
             '\n' => {},
-            _ => return Err(CfuaError::InvalidChar),
+            _ => return Err(CfuaError::InvalidChar(self.span())),
         }
 
         Ok(())
@@ -406,19 +836,378 @@ impl ParserData {
         }
     }
 
-    /// Parses string given while creating structure.
-    pub fn parse(&mut self) -> Result<Cfua, CfuaError> {
-        let input = self.input.clone();
-        let mut chars = input.chars();
+    /// Reads single `line` of input, which should end with newline
+    /// unless it is the last line.
+    pub fn feed_line(&mut self, line: &str) -> Result<(), CfuaError> {
+        self.line.clear();
+        self.line.push_str(line);
 
-        while let Some(char) = chars.next() {
+        for char in line.chars() {
             self.read_char(char)?;
+            self.advance(char);
+        }
+
+        Ok(())
+    }
+
+    /// Drops partially read value and skips rest of `line`, which was
+    /// being read from `start`, so that parsing can continue from the next line.
+    fn skip_line(&mut self, start: Cursor, line: &str) {
+        // string value is complete unless continued in following lines
+        if self.state == State::Reading {
+            let _ = self.push_pending_string();
+        }
+        self.key_buffer.clear();
+        self.value_buffer.clear();
+        self.comment_buffer.clear();
+        self.held_comments.clear();
+        self.array_len = 0;
+        self.array_type = None;
+        self.state = State::Reading;
+
+        self.cursor = start;
+        for char in line.chars() {
+            self.advance(char);
+        }
+    }
+
+    /// Parses whole `input` string, continuing after errors. Line containing
+    /// an error is skipped, together with following lines up to the next
+    /// key, section or comment. After an invalid section name, lines are
+    /// skipped up to the next section, so that keys of that section are
+    /// not read into the previous one. Returns data read from remaining
+    /// lines and all errors found.
+    pub fn parse_recovering(mut self, input: &str) -> (Cfua, Vec<CfuaError>) {
+        let mut builder = Builder::new();
+        let mut errors = Vec::new();
+        let mut skipping = false;
+        let mut skipping_section = false;
+
+        for line in input.split_inclusive('\n') {
+            let start = self.cursor;
+            if (skipping && !line.starts_with(starts_entry))
+                || (skipping_section && !line.starts_with('@')) {
+                self.skip_line(start, line);
+                continue;
+            }
+            skipping = false;
+            skipping_section = false;
+
+            if let Err(e) = self.feed_line(line) {
+                errors.push(e);
+                skipping_section = self.state == State::SectionName;
+                skipping = true;
+                self.skip_line(start, line);
+            }
+            builder.consume(&mut self);
+        }
+
+        if let Err(e) = self.end_input() {
+            errors.push(e);
+        }
+        builder.consume(&mut self);
+        (builder.finish(), errors)
+    }
+
+    /// Parses whole `input` string.
+    pub fn parse(mut self, input: &str) -> Result<Cfua, CfuaError> {
+        let mut builder = Builder::new();
+        for line in input.split_inclusive('\n') {
+            self.feed_line(line)?;
+            builder.consume(&mut self);
+        }
+
+        self.end_input()?;
+        builder.consume(&mut self);
+        Ok(builder.finish())
+    }
+
+    /// Parses input from `reader`, reading it line by line.
+    pub fn parse_reader<R>(mut self, mut reader: R) -> Result<Cfua, CfuaError>
+    where R: BufRead {
+        let mut builder = Builder::new();
+        let mut line = String::with_capacity(256);
+
+        loop {
+            line.clear();
+            if reader.read_line(&mut line).map_err(CfuaError::IoError)? == 0 {
+                break;
+            }
+            self.feed_line(&line)?;
+            builder.consume(&mut self);
         }
 
-        if self.value_buffer.len() > 0 {
+        self.end_input()?;
+        builder.consume(&mut self);
+        Ok(builder.finish())
+    }
+
+    /// Emits events for input left unfinished in the last line,
+    /// which has no trailing newline.
+    pub(crate) fn end_input(&mut self) -> Result<(), CfuaError> {
+        if let State::ArraySimple | State::ArrayNormal(_) = self.state {
+            return Err(CfuaError::UnclosedArray(self.span()));
+        }
+        if let State::SectionName | State::Comment = self.state {
+            self.read_char('\n')?;
+        }
+        if !self.value_buffer.is_empty() {
             self.push_value()?;
         }
+        Ok(())
+    }
+}
+
+/// Returns whether `char` at the beginning of a line starts a key,
+/// section or comment, i.e. an entry at which recovering parse resumes.
+fn starts_entry(char: char) -> bool {
+    matches!(char, 'a'..='z' | '@' | '%')
+}
+
+impl Builder {
+    fn new() -> Self {
+        Self {
+            data: Cfua::create(),
+            section: None,
+            key: String::new(),
+            key_comment: None,
+            array: Vec::new(),
+            array_radix: Radix::Decimal,
+            comment: None,
+        }
+    }
+
+    /// Takes all events queued in `parser`.
+    fn consume(&mut self, parser: &mut ParserData) {
+        while let Some((event, radix)) = parser.next_event_with_radix() {
+            self.push(event, radix);
+        }
+    }
+
+    fn push(&mut self, event: Event, radix: Radix) {
+        match event {
+            Event::SectionStart(name) => {
+                self.finish_section();
+                self.section = Some((name, Cfua::create(), self.comment.take()));
+            },
+            Event::Key(key) => {
+                self.key_comment = self.comment.take();
+                self.key = key;
+            },
+            Event::Value(value) => self.store(value, radix),
+            Event::ArrayStart => self.array.clear(),
+            Event::ArrayElement(value) => {
+                if self.array.is_empty() {
+                    self.array_radix = radix;
+                }
+                self.array.push(value);
+            },
+            Event::ArrayEnd => {
+                let array = std::mem::take(&mut self.array);
+                self.store(CfuaType::Array(array), self.array_radix);
+            },
+            Event::Comment(text) => {
+                let text = text.strip_prefix(' ').unwrap_or(&text);
+                match &mut self.comment {
+                    Some(comment) => {
+                        comment.push('\n');
+                        comment.push_str(text);
+                    },
+                    None => self.comment = Some(text.to_string()),
+                }
+            },
+        }
+    }
+
+    /// Stores `value` with last read key and `radix` of its literal,
+    /// either in current section or, if none was declared yet,
+    /// at the top level.
+    fn store(&mut self, value: CfuaType, radix: Radix) {
+        // comments placed inside of a value (between lines of multiline
+        // string) are not attached to anything
+        self.comment = None;
+        let key = std::mem::take(&mut self.key);
+        let comment = self.key_comment.take();
+        let target = self.target();
+        target.write_value(key, value);
+        let index = target.len() - 1;
+        target.set_comment_at(index, comment);
+        target.set_radix_at(index, radix);
+    }
+
+    /// Returns structure into which values are currently read.
+    fn target(&mut self) -> &mut Cfua {
+        match &mut self.section {
+            Some((_, section, _)) => section,
+            None => &mut self.data,
+        }
+    }
+
+    /// Moves currently read section (if any) into parsed data.
+    fn finish_section(&mut self) {
+        if let Some((name, section, comment)) = self.section.take() {
+            // sections read from input are never nested
+            self.data.write_value(name, CfuaType::Section(section));
+            self.data.set_comment_at(self.data.len() - 1, comment);
+        }
+    }
+
+    fn finish(mut self) -> Cfua {
+        self.finish_section();
+        self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_span() {
+        let example =
+r"name: 'cfua
+port number: 1
+";
+        let error = ParserData::new().parse(example).unwrap_err();
+
+        assert!(matches!(error, CfuaError::InvalidKeyChar(' ', _)));
+        assert_eq!(error.span(), Some(&Span {
+            line: 2,
+            column: 5,
+            offset: 16,
+            source_line: "port number: 1".to_string(),
+        }));
+    }
+
+    #[test]
+    fn error_span_points_at_value() {
+        let example =
+r"first: 1
+second: maybe
+";
+        let error = ParserData::new().parse(example).unwrap_err();
+
+        assert!(matches!(error, CfuaError::UnknownKeyword(_, _)));
+        let span = error.span().unwrap();
+        assert_eq!((span.line, span.column, span.offset), (2, 9, 17));
+    }
+
+    #[test]
+    fn error_display_snippet() {
+        let example =
+r"name: 'cfua
+port number: 1
+";
+        let error = ParserData::new().parse(example).unwrap_err();
+
+        let expected =
+r"invalid char: ' ' in key name
+ --> 2:5
+  |
+2 | port number: 1
+  |     ^";
+        assert_eq!(error.to_string(), expected);
+    }
+
+    #[test]
+    fn full_char_ranges() {
+        let example =
+r"zone: 9
+sizes: [9, 19]
+max: -9
+";
+        let data = ParserData::new().parse(example).unwrap();
+
+        assert_eq!(data.read_integer("zone"), Some(9));
+        assert_eq!(data.read_array("sizes"), Some(vec![CfuaType::Integer(9), CfuaType::Integer(19)]));
+        assert_eq!(data.read_integer("max"), Some(-9));
+    }
+
+    #[test]
+    fn malformed_numbers() {
+        let parse = |input: &str| ParserData::new().parse(input);
+
+        #[cfg(not(feature = "bigint"))]
+        {
+            assert!(matches!(parse("x: 99999999999999999999\n"), Err(CfuaError::IntegerOverflow(_, _))));
+            assert!(matches!(parse("x: -h8000000000000001\n"), Err(CfuaError::IntegerOverflow(_, _))));
+        }
+        assert!(matches!(parse("x: h\n"), Err(CfuaError::EmptyRadixLiteral(_, _))));
+        assert!(matches!(parse("x: -b\n"), Err(CfuaError::EmptyRadixLiteral(_, _))));
+        assert!(matches!(parse("x: 1.2.3\n"), Err(CfuaError::MalformedFloat(_, _))));
+        assert!(matches!(parse("x: 12a\n"), Err(CfuaError::MalformedInteger(_, _))));
+        assert!(matches!(parse("x: -\n"), Err(CfuaError::MalformedInteger(_, _))));
+        assert!(matches!(parse("x: [1, 2.5.1]\n"), Err(CfuaError::MalformedFloat(_, _))));
+        assert!(matches!(parse("x: [1, h]\n"), Err(CfuaError::EmptyRadixLiteral(_, _))));
+        assert!(matches!(parse("x: [\n#3\n]\n"), Err(CfuaError::InvalidArrayValue(_, _))));
+        assert!(matches!(parse("x: [\n#'a:"), Err(CfuaError::UnclosedArray(_))));
+    }
+
+    #[test]
+    fn string_in_simple_array() {
+        let parse = |input: &str| ParserData::new().parse(input);
 
-        Ok(self.data.clone())
+        assert!(matches!(parse("x: ['a]\n"), Err(CfuaError::StringInSimpleArray(_))));
+        assert!(matches!(parse("x: [1, 'a]\n"), Err(CfuaError::StringInSimpleArray(_))));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn mixed_array_type() {
+        let parse = |input: &str| ParserData::new().parse(input);
+
+        assert!(matches!(parse("x: [1, 2.5]\n"), Err(CfuaError::MixedArrayType(_))));
+        let error = parse("x: [1, 2, 3.5, 4]\n").unwrap_err();
+        let span = error.span().unwrap();
+        assert_eq!((span.line, span.column), (1, 11));
+
+        // integers of any size have the same type
+        assert!(parse("x: [1, 9223372036854775808]\n").is_ok());
+        assert!(parse("x: [1.5, inf, -inf]\n").is_ok());
+    }
+
+    #[test]
+    fn number_limits() {
+        let data = ParserData::new()
+            .parse("min: -h8000000000000000\nmax: 9223372036854775807\nmask: [hff, b11]\n")
+            .unwrap();
+
+        assert_eq!(data.read_integer("min"), Some(i64::MIN));
+        assert_eq!(data.read_integer("max"), Some(i64::MAX));
+        assert_eq!(data.read_array("mask"), Some(vec![CfuaType::Integer(255), CfuaType::Integer(3)]));
+
+        let data = ParserData::new()
+            .parse("id: hFFFFFFFFFFFFFFFF
+ids: [1, 9223372036854775808]
+")
+            .unwrap();
+
+        assert_eq!(data.read_value("id"), Some(&CfuaType::Unsigned(u64::MAX)));
+        assert_eq!(data.read_u64("id"), Some(u64::MAX));
+        assert_eq!(data.read_integer("id"), None);
+        assert_eq!(data.get::<Vec<u64>>("ids").unwrap(), vec![1, 1 << 63]);
+        assert!(matches!(data.get_u64("ids"), Err(CfuaError::TypeMismatch { .. })));
+        assert_eq!(data.to_string(), "id: 18446744073709551615\nids: [1, 9223372036854775808]\n");
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn big_integers() {
+        use num_bigint::BigInt;
+
+        let data = ParserData::new()
+            .parse("big: 99999999999999999999\nnegative: -h8000000000000001\n")
+            .unwrap();
+        let big: BigInt = "99999999999999999999".parse().unwrap();
+
+        assert_eq!(data.read_value("big"), Some(&CfuaType::BigInt(big.clone())));
+        assert_eq!(data.get::<u128>("big").unwrap(), 99999999999999999999);
+        assert_eq!(data.get::<i128>("negative").unwrap(), -0x8000000000000001);
+        assert!(data.get::<u64>("big").is_err());
+
+        let mut written = Cfua::create();
+        written.write("big", big).unwrap();
+        written.write("small", 5u128).unwrap();
+        assert_eq!(written.read_integer("small"), Some(5));
+        assert_eq!(written.to_string(), "big: 99999999999999999999\nsmall: 5\n");
+    }
+}
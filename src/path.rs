@@ -0,0 +1,114 @@
+use crate::{cfua::CfuaType, Cfua};
+
+/// Single step of a query path.
+#[derive(Debug, PartialEq)]
+enum Segment<'a> {
+    /// Name of a key or section, separated with dot `.`.
+    Key(&'a str),
+    /// Array index, written in brackets `[n]`.
+    Index(usize),
+}
+
+/// Splits `path` into segments. Returns `None` if path is malformed.
+fn parse_path(path: &str) -> Option<Vec<Segment<'_>>> {
+    let mut segments = Vec::new();
+
+    for part in path.split('.') {
+        let (key, mut indices) = part.split_at(part.find('[').unwrap_or(part.len()));
+        if key.is_empty() {
+            return None;
+        }
+        segments.push(Segment::Key(key));
+
+        while !indices.is_empty() {
+            let inner = indices.strip_prefix('[')?;
+            let end = inner.find(']')?;
+            segments.push(Segment::Index(inner[..end].parse().ok()?));
+            indices = &inner[end + 1..];
+        }
+    }
+
+    Some(segments)
+}
+
+impl Cfua {
+    /// Looks up value with a `path` expression, which may go through
+    /// sections and array elements.
+    /// 
+    /// Path consists of key names separated with dot `.`, each optionally
+    /// followed by array indices in brackets, e.g. `server.port`,
+    /// `hosts[2]` or `server.hosts[0]`. Returns `None` if path is malformed
+    /// or does not lead to any value.
+    /// 
+    /// ## Example
+    /// 
+    /// ```
+    /// use cfua::Cfua;
+    /// 
+    /// let data = Cfua::from_string("hosts: [1, 2, 4]\n@server\nport: 8080\n").unwrap();
+    /// 
+    /// assert_eq!(data.query("server.port").and_then(|v| v.as_integer()), Some(8080));
+    /// assert_eq!(data.query("hosts[2]").and_then(|v| v.as_integer()), Some(4));
+    /// ```
+    pub fn query<P>(&self, path: P) -> Option<&CfuaType>
+    where P: AsRef<str> {
+        let mut segments = parse_path(path.as_ref())?.into_iter();
+        let Some(Segment::Key(first)) = segments.next() else {
+            return None;
+        };
+
+        let mut value = self.find(first)?;
+        for segment in segments {
+            value = match (segment, value) {
+                (Segment::Key(key), CfuaType::Section(section)) => section.find(key)?,
+                (Segment::Index(i), CfuaType::Array(array)) => array.get(i)?,
+                _ => return None,
+            };
+        }
+
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_parsing() {
+        assert_eq!(parse_path("server.hosts[1][2]"), Some(vec![
+            Segment::Key("server"),
+            Segment::Key("hosts"),
+            Segment::Index(1),
+            Segment::Index(2),
+        ]));
+        assert_eq!(parse_path(""), None);
+        assert_eq!(parse_path("server."), None);
+        assert_eq!(parse_path("hosts[x]"), None);
+        assert_eq!(parse_path("hosts[1"), None);
+        assert_eq!(parse_path("[1]"), None);
+    }
+
+    #[test]
+    fn query_values() {
+        let example =
+r"hosts: [
+#'alpha
+#'beta
+]
+@server
+port: 8080
+@database
+port: 5432
+";
+        let data = Cfua::from_string(example).unwrap();
+
+        assert_eq!(data.query("server.port"), Some(&CfuaType::Integer(8080)));
+        assert_eq!(data.query("database.port"), Some(&CfuaType::Integer(5432)));
+        assert_eq!(data.query("hosts[1]").and_then(|v| v.as_str()), Some("beta"));
+        assert_eq!(data.query("hosts[2]"), None);
+        assert_eq!(data.query("port"), None);
+        assert_eq!(data.query("server.port.value"), None);
+        assert!(data.query("server").and_then(|v| v.as_section()).is_some());
+    }
+}
@@ -1,4 +1,4 @@
-use std::{fs, path::Path};
+use std::{fs::File, io::{BufRead, BufReader}, path::Path};
 
 use crate::{parser::{CfuaError, ParserData}, Cfua};
 
@@ -8,8 +8,8 @@ impl Cfua {
     /// or if file content is not proper cfua data.
     pub fn from_file_path<P>(path: P) -> Result<Cfua, CfuaError>
     where P: AsRef<Path> {
-        match fs::read_to_string(path) {
-            Ok(content) => Cfua::from_string(content),
+        match File::open(path) {
+            Ok(file) => Cfua::from_reader(BufReader::new(file)),
             Err(e) => Err(CfuaError::IoError(e)),
         }
     }
@@ -18,13 +18,38 @@ impl Cfua {
     /// content is not proper cfua data.
     pub fn from_string<S>(string: S) -> Result<Cfua, CfuaError>
     where S: ToString {
-        ParserData::new(string.to_string()).parse()
+        ParserData::new().parse(&string.to_string())
+    }
+
+    /// Reads cfua data from string, continuing after errors, so that all
+    /// mistakes can be reported at once. Returns data read from valid
+    /// lines, along with all errors found (empty if string content
+    /// is proper cfua data).
+    ///
+    /// After an error, the rest of its line is skipped, together with
+    /// following lines up to the next key, section or comment (e.g. the
+    /// rest of an array). After an invalid section name, whole section
+    /// is skipped up to the next one. Values which were not completely
+    /// read are left out.
+    pub fn from_string_recovering<S>(string: S) -> (Cfua, Vec<CfuaError>)
+    where S: ToString {
+        ParserData::new().parse_recovering(&string.to_string())
+    }
+
+    /// Reads cfua data from `reader` line by line, so that whole input
+    /// is never kept in memory. Works with any source, including pipes
+    /// and sockets. Will return error if there is a problem with reading
+    /// (including input not being valid UTF-8) or if content is not
+    /// proper cfua data.
+    pub fn from_reader<R>(reader: R) -> Result<Cfua, CfuaError>
+    where R: BufRead {
+        ParserData::new().parse_reader(reader)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::array::{CfuaIntegerArray, CfuaStringArray};
+    use crate::{array::{CfuaIntegerArray, CfuaStringArray}, CfuaType};
 
     use super::*;
 
@@ -89,4 +114,91 @@ apostrophes: '''''''
 
         assert_eq!(Cfua::from_string(example).unwrap(), structure);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn from_string_sections() {
+        let example =
+r"name: 'example
+@server
+port: 8080
+greeting: 'Hello
+@database
+port: 5432
+";
+        let data = Cfua::from_string(example).unwrap();
+
+        assert_eq!(data.sections(), vec!["server".to_string(), "database".to_string()]);
+        assert_eq!(data.read_string("name"), Some("example".to_string()));
+        assert_eq!(data.read_integer("port"), None);
+
+        let server = data.read_section("server").unwrap();
+        assert_eq!(server.read_integer("port"), Some(8080));
+        assert_eq!(server.read_string("greeting"), Some("Hello".to_string()));
+        assert_eq!(data.read_section("database").unwrap().read_integer("port"), Some(5432));
+    }
+
+    #[test]
+    fn from_reader_chunked() {
+        let example = "name: 'First\n'Second\ncounts: [1, 2, 3]\n@server\nport: 8080";
+        // tiny buffer makes sure lines are assembled from many reads
+        let reader = BufReader::with_capacity(3, example.as_bytes());
+
+        assert_eq!(Cfua::from_reader(reader).unwrap(), Cfua::from_string(example).unwrap());
+    }
+
+    #[test]
+    fn from_reader_errors() {
+        let error = Cfua::from_reader(&b"name: '\xff\n"[..]).unwrap_err();
+        assert!(matches!(error, CfuaError::IoError(_)));
+
+        let error = Cfua::from_reader(&b"first: 1\nsecond: maybe\n"[..]).unwrap_err();
+        let span = error.span().unwrap();
+        assert_eq!((span.line, span.column, span.source_line.as_str()), (2, 9, "second: maybe"));
+    }
+
+    #[test]
+    fn from_string_recovering() {
+        let example =
+r"name: 'example
+port: 80x
+hosts: [
+#'alpha
+#3
+]
+@server
+Bad: 1
+debug: true
+level: 3
+";
+        let (data, errors) = Cfua::from_string_recovering(example);
+
+        let mut server = Cfua::create();
+        server.write_bool("debug", true);
+        server.write_integer("level", 3);
+        let mut expected = Cfua::create();
+        expected.write_string("name", "example");
+        expected.write_section("server", server).unwrap();
+        assert_eq!(data, expected);
+
+        assert!(matches!(errors[0], CfuaError::MalformedInteger(..)));
+        assert!(matches!(errors[1], CfuaError::InvalidArrayValue(..)));
+        assert!(matches!(errors[2], CfuaError::InvalidChar(..)));
+        let lines: Vec<_> = errors.iter().map(|e| e.span().unwrap().line).collect();
+        assert_eq!(lines, vec![2, 5, 8]);
+
+        // keys following invalid section name are not read into previous section
+        let (data, errors) = Cfua::from_string_recovering("@server\nport: 1\n@Data base\nport: 2\n@client\nport: 3\n");
+        assert!(matches!(errors[..], [CfuaError::InvalidSectionChar('D', _)]));
+        assert_eq!(data.query("server.port").and_then(CfuaType::as_integer), Some(1));
+        assert_eq!(data.read_section_ref("server").map(Cfua::len), Some(1));
+        assert_eq!(data.query("client.port").and_then(CfuaType::as_integer), Some(3));
+
+        // parsing resumes at keys starting with any letter
+        let (data, errors) = Cfua::from_string_recovering("a: 1x\nz: 2\n");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(data.read_integer("z"), Some(2));
+
+        let (_, errors) = Cfua::from_string_recovering("a: 1\n");
+        assert!(errors.is_empty());
+    }
+}
@@ -0,0 +1,484 @@
+//! Serializing Rust types into cfua data with serde.
+//!
+//! Top-level value must be a struct or a map, which becomes top-level
+//! data. Nested structs and maps become sections, sequences become
+//! arrays, and `None` values are skipped. Unit enum variants are
+//! written as strings. Values which cannot be written as cfua, such
+//! as empty sequences, are rejected with [`CfuaError::Conversion`], and
+//! keys which are not valid cfua names with [`CfuaError::InvalidKey`].
+//! As sections cannot be nested, structs and maps inside of a section
+//! are rejected too.
+//!
+//! ## Example
+//!
+//! ```
+//! use serde::Serialize;
+//!
+//! #[derive(Serialize)]
+//! struct Server {
+//!     port: u16,
+//! }
+//!
+//! #[derive(Serialize)]
+//! struct Config {
+//!     name: String,
+//!     server: Server,
+//! }
+//!
+//! let config = Config { name: "example".to_string(), server: Server { port: 8080 } };
+//! assert_eq!(cfua::to_string(&config).unwrap(), "name: 'example\n@server\nport: 8080\n");
+//! ```
+
+use std::fmt::Display;
+
+use serde::ser::{self, Impossible, Serialize};
+
+use crate::{cfua::{check_key, CfuaType}, convert::IntoCfua, Cfua, CfuaError};
+
+impl ser::Error for CfuaError {
+    fn custom<T>(msg: T) -> Self
+    where T: Display {
+        CfuaError::Serde { key: String::new(), message: msg.to_string() }
+    }
+}
+
+fn error(message: &str) -> CfuaError {
+    <CfuaError as ser::Error>::custom(message)
+}
+
+/// Serializes `value` into string containing cfua data.
+pub fn to_string<T>(value: &T) -> Result<String, CfuaError>
+where T: Serialize + ?Sized {
+    Ok(to_cfua(value)?.to_string())
+}
+
+/// Serializes `value` into [`Cfua`] structure, which is checked
+/// to be writable (see [`Cfua::validate`]).
+pub fn to_cfua<T>(value: &T) -> Result<Cfua, CfuaError>
+where T: Serialize + ?Sized {
+    match value.serialize(Serializer)? {
+        Some(CfuaType::Section(data)) => {
+            data.validate()?;
+            Ok(data)
+        },
+        _ => Err(error("top-level value must be a struct or a map")),
+    }
+}
+
+/// Serializer producing single cfua value. `None` is returned for values
+/// which should not be written, like `Option::None` or unit.
+pub struct Serializer;
+
+impl ser::Serializer for Serializer {
+    type Ok = Option<CfuaType>;
+    type Error = CfuaError;
+
+    type SerializeSeq = SerializeArray;
+    type SerializeTuple = SerializeArray;
+    type SerializeTupleStruct = SerializeArray;
+    type SerializeTupleVariant = Impossible<Self::Ok, Self::Error>;
+    type SerializeMap = SerializeSection;
+    type SerializeStruct = SerializeSection;
+    type SerializeStructVariant = Impossible<Self::Ok, Self::Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+        Ok(Some(CfuaType::Bool(v)))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+        Ok(Some(CfuaType::Integer(v)))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+        v.into_cfua()
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
+        v.into_cfua()
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Self::Ok, Self::Error> {
+        v.into_cfua()
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
+        self.serialize_f64(v.into())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
+        Ok(Some(CfuaType::Float(v)))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+        Ok(Some(CfuaType::String(v.to_string())))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
+        Ok(Some(CfuaType::String(v.to_string())))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        Ok(Some(CfuaType::Array(v.iter().map(|b| CfuaType::Integer((*b).into())).collect())))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        Ok(None)
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Self::Error>
+    where T: Serialize + ?Sized {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        Ok(None)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok, Self::Error> {
+        Ok(None)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<Self::Ok, Self::Error>
+    where T: Serialize + ?Sized {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _value: &T,
+    ) -> Result<Self::Ok, Self::Error>
+    where T: Serialize + ?Sized {
+        Err(error(&format!("enum variant {name}::{variant} with data is not supported")))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        Ok(SerializeArray { elements: Vec::with_capacity(len.unwrap_or(0)) })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Err(error(&format!("enum variant {name}::{variant} with data is not supported")))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        Ok(SerializeSection { data: Cfua::create(), key: None })
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct, Self::Error> {
+        self.serialize_map(None)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Err(error(&format!("enum variant {name}::{variant} with data is not supported")))
+    }
+}
+
+/// Collects array elements.
+pub struct SerializeArray {
+    elements: Vec<CfuaType>,
+}
+
+impl SerializeArray {
+    fn push<T>(&mut self, value: &T) -> Result<(), CfuaError>
+    where T: Serialize + ?Sized {
+        let index = self.elements.len();
+        match value.serialize(Serializer)? {
+            Some(CfuaType::Array(_)) => Err(error("nested arrays are not allowed").at_key(&format!("[{index}]"))),
+            Some(CfuaType::Section(_)) => Err(error("arrays of sections are not allowed").at_key(&format!("[{index}]"))),
+            Some(element) => {
+                self.elements.push(element);
+                Ok(())
+            },
+            None => Err(error("arrays cannot hold empty values").at_key(&format!("[{index}]"))),
+        }
+    }
+}
+
+impl ser::SerializeSeq for SerializeArray {
+    type Ok = Option<CfuaType>;
+    type Error = CfuaError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where T: Serialize + ?Sized {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Some(CfuaType::Array(self.elements)))
+    }
+}
+
+impl ser::SerializeTuple for SerializeArray {
+    type Ok = Option<CfuaType>;
+    type Error = CfuaError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where T: Serialize + ?Sized {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Some(CfuaType::Array(self.elements)))
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeArray {
+    type Ok = Option<CfuaType>;
+    type Error = CfuaError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where T: Serialize + ?Sized {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Some(CfuaType::Array(self.elements)))
+    }
+}
+
+/// Collects key-value pairs of top-level data or a section.
+pub struct SerializeSection {
+    data: Cfua,
+    key: Option<String>,
+}
+
+impl SerializeSection {
+    fn insert<T>(&mut self, key: &str, value: &T) -> Result<(), CfuaError>
+    where T: Serialize + ?Sized {
+        check_key(key)?;
+        match value.serialize(Serializer).map_err(|e| e.at_key(key))? {
+            Some(CfuaType::Section(section)) => self.data.write_section(key, section).map_err(|e| match e {
+                CfuaError::NestedSection(name) => error("sections cannot be nested").at_key(&name).at_key(key),
+                other => other,
+            })?,
+            Some(value) => self.data.write_value(key, value),
+            None => {},
+        }
+        Ok(())
+    }
+}
+
+impl ser::SerializeMap for SerializeSection {
+    type Ok = Option<CfuaType>;
+    type Error = CfuaError;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Self::Error>
+    where T: Serialize + ?Sized {
+        match key.serialize(Serializer)? {
+            Some(CfuaType::String(key)) => {
+                self.key = Some(key);
+                Ok(())
+            },
+            _ => Err(error("map keys must be strings")),
+        }
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Self::Error>
+    where T: Serialize + ?Sized {
+        let key = self.key.take().ok_or_else(|| error("value serialized before key"))?;
+        self.insert(&key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Some(CfuaType::Section(self.data)))
+    }
+}
+
+impl ser::SerializeStruct for SerializeSection {
+    type Ok = Option<CfuaType>;
+    type Error = CfuaError;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Self::Error>
+    where T: Serialize + ?Sized {
+        self.insert(key, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Self::Error> {
+        Ok(Some(CfuaType::Section(self.data)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Serialize;
+
+    use super::*;
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "kebab-case")]
+    enum Mode {
+        Release,
+    }
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "kebab-case")]
+    struct Server {
+        port: u16,
+        hosts: Vec<&'static str>,
+        max_connections: Option<u32>,
+    }
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "kebab-case")]
+    struct Config {
+        server: Server,
+        name: String,
+        mode: Mode,
+        weights: [f64; 2],
+    }
+
+    #[test]
+    fn serialize_struct() {
+        let config = Config {
+            server: Server {
+                port: 8080,
+                hosts: vec!["alpha", "beta"],
+                max_connections: None,
+            },
+            name: "example".to_string(),
+            mode: Mode::Release,
+            weights: [0.5, 0.25],
+        };
+
+        let example =
+r"name: 'example
+mode: 'release
+weights: [0.5, 0.25]
+@server
+port: 8080
+hosts: [
+#'alpha
+#'beta
+]
+";
+        assert_eq!(to_string(&config).unwrap(), example);
+    }
+
+    #[test]
+    fn unsupported_values() {
+        let error = to_string(&vec![1, 2]).unwrap_err();
+        assert!(matches!(&error, CfuaError::Serde { key, .. } if key.is_empty()));
+
+        #[derive(Serialize)]
+        struct Nested {
+            matrix: Vec<Vec<i32>>,
+        }
+        let error = to_string(&Nested { matrix: vec![vec![1]] }).unwrap_err();
+        assert!(matches!(&error, CfuaError::Serde { key, .. } if key == "matrix[0]"));
+
+        #[derive(Serialize)]
+        struct Big {
+            value: u64,
+        }
+        assert_eq!(to_string(&Big { value: u64::MAX }).unwrap(), "value: 18446744073709551615\n");
+
+        #[derive(Serialize)]
+        struct Wide {
+            value: u128,
+        }
+        assert_eq!(to_string(&Wide { value: 5 }).unwrap(), "value: 5\n");
+        #[cfg(not(feature = "bigint"))]
+        assert!(matches!(
+            to_string(&Wide { value: u128::MAX }),
+            Err(CfuaError::OutOfRange { key, .. }) if key == "value"
+        ));
+        #[cfg(feature = "bigint")]
+        assert_eq!(to_string(&Wide { value: u128::MAX }).unwrap(), format!("value: {}\n", u128::MAX));
+
+        #[derive(Serialize)]
+        struct Empty {
+            hosts: Vec<String>,
+        }
+        let error = to_string(&Empty { hosts: Vec::new() }).unwrap_err();
+        assert!(matches!(&error, CfuaError::Conversion { key, .. } if key == "hosts"));
+
+        #[derive(Serialize)]
+        struct Mixed {
+            pair: (i32, &'static str),
+        }
+        let error = to_string(&Mixed { pair: (1, "one") }).unwrap_err();
+        assert!(matches!(&error, CfuaError::Conversion { key, .. } if key == "pair"));
+    }
+
+    #[test]
+    fn invalid_structure() {
+        #[derive(Serialize)]
+        struct Tls {
+            enabled: bool,
+        }
+        #[derive(Serialize)]
+        struct Listener {
+            tls: Tls,
+        }
+        #[derive(Serialize)]
+        struct Outer {
+            listener: Listener,
+        }
+        let error = to_string(&Outer { listener: Listener { tls: Tls { enabled: true } } }).unwrap_err();
+        assert!(matches!(&error, CfuaError::Serde { key, .. } if key == "listener.tls"));
+
+        #[derive(Serialize)]
+        struct Invalid {
+            #[serde(rename = "Port")]
+            port: u16,
+        }
+        let error = to_string(&Invalid { port: 80 }).unwrap_err();
+        assert!(matches!(&error, CfuaError::InvalidKey(key) if key == "Port"));
+        assert!(to_string(&std::collections::BTreeMap::from([("max_size", 1)])).is_err());
+    }
+}
@@ -0,0 +1,253 @@
+//! Conversion between cfua data and TOML, available with `toml` feature.
+//!
+//! Top-level tables are converted into sections, and other values into
+//! cfua values of the same type. Both conversions return all constructs
+//! which could not be represented, as [`CfuaError::Conversion`], along
+//! with data which could:
+//! - tables nested in tables, arrays of tables, nested arrays, empty
+//!   arrays and arrays mixing value types are left out when reading TOML,
+//! - dates and times are read as strings, and reported as well,
+//! - keys are renamed like in [`Cfua::from_ini`], and invalid keys are
+//!   left out,
+//! - integers outside of `i64` range are left out when writing TOML,
+//! - comments are left out when writing TOML. TOML comments are not
+//!   read, so they are lost without being reported.
+//!
+//! ## Example
+//!
+//! ```
+//! use cfua::Cfua;
+//!
+//! let (data, diagnostics) = Cfua::from_toml("name = \"example\"\n\n[server]\nport = 8080\n").unwrap();
+//!
+//! assert_eq!(data.query("server.port").and_then(|v| v.as_integer()), Some(8080));
+//! assert!(diagnostics.is_empty());
+//!
+//! let (toml, diagnostics) = data.to_toml();
+//! assert_eq!(toml, "name = \"example\"\n\n[server]\nport = 8080\n");
+//! assert!(diagnostics.is_empty());
+//! ```
+
+use toml::{Table, Value};
+
+use crate::{migrate::{diagnostic, import_key, join}, Cfua, CfuaError, CfuaType};
+
+impl Cfua {
+    /// Reads TOML data, leaving out constructs which cannot be
+    /// represented. Returns data read, along with all diagnostics
+    /// (empty if whole input was converted), or an error if `input`
+    /// is not valid TOML.
+    pub fn from_toml(input: &str) -> Result<(Cfua, Vec<CfuaError>), CfuaError> {
+        let table: Table = input.parse()
+            .map_err(|e: toml::de::Error| diagnostic("", e))?;
+        let mut diagnostics = Vec::new();
+        let data = import_table(&table, None, &mut diagnostics);
+        Ok((data, diagnostics))
+    }
+
+    /// Writes data as TOML, leaving out values which cannot be
+    /// represented. Returns TOML text, along with all diagnostics
+    /// (empty if all data was written).
+    pub fn to_toml(&self) -> (String, Vec<CfuaError>) {
+        let mut diagnostics = Vec::new();
+        let table = export_table(self, None, &mut diagnostics);
+        let output = toml::to_string(&table).unwrap_or_else(|e| {
+            diagnostics.push(diagnostic("", e));
+            String::new()
+        });
+        (output, diagnostics)
+    }
+}
+
+/// Converts `table` into cfua data. Tables inside of a `section`
+/// are left out, as sections cannot be nested.
+fn import_table(table: &Table, section: Option<&str>, diagnostics: &mut Vec<CfuaError>) -> Cfua {
+    let mut data = Cfua::create();
+    for (name, value) in table {
+        let path = join(section, name);
+        let Some(key) = import_key(name, section, diagnostics) else {
+            continue;
+        };
+        let value = match value {
+            Value::Table(_) if section.is_some() => {
+                diagnostics.push(diagnostic(&path, "nested tables cannot be represented in cfua"));
+                continue;
+            },
+            Value::Table(inner) => CfuaType::Section(import_table(inner, Some(name), diagnostics)),
+            other => match import_value(other, &path, diagnostics) {
+                Some(value) => value,
+                None => continue,
+            },
+        };
+        if data.read_value(&key).is_some() {
+            diagnostics.push(diagnostic(&path, "duplicate key is left out"));
+            continue;
+        }
+        data.write_value(key, value);
+    }
+    data
+}
+
+/// Converts TOML value (other than table) at `path` into cfua value.
+fn import_value(value: &Value, path: &str, diagnostics: &mut Vec<CfuaError>) -> Option<CfuaType> {
+    Some(match value {
+        Value::String(value) => CfuaType::String(value.clone()),
+        Value::Integer(value) => CfuaType::Integer(*value),
+        Value::Float(value) => CfuaType::Float(*value),
+        Value::Boolean(value) => CfuaType::Bool(*value),
+        Value::Datetime(value) => {
+            diagnostics.push(diagnostic(path, "date and time converted into string"));
+            CfuaType::String(value.to_string())
+        },
+        Value::Array(elements) => return import_array(elements, path, diagnostics),
+        Value::Table(_) => {
+            diagnostics.push(diagnostic(path, "tables inside of arrays cannot be represented in cfua"));
+            return None;
+        },
+    })
+}
+
+fn import_array(elements: &[Value], path: &str, diagnostics: &mut Vec<CfuaError>) -> Option<CfuaType> {
+    if elements.is_empty() {
+        diagnostics.push(diagnostic(path, "empty arrays cannot be represented in cfua"));
+        return None;
+    }
+    let mut array = Vec::with_capacity(elements.len());
+    for (index, element) in elements.iter().enumerate() {
+        let path = format!("{path}[{index}]");
+        if let Value::Array(_) = element {
+            diagnostics.push(diagnostic(&path, "nested arrays cannot be represented in cfua"));
+            return None;
+        }
+        array.push(import_value(element, &path, diagnostics)?);
+    }
+
+    if let Some(first) = array.first()
+        && let Some(other) = array.iter().find(|value| value.type_name() != first.type_name()) {
+        diagnostics.push(diagnostic(path, format!("array mixing {} and {} values cannot be represented in cfua", first.type_name(), other.type_name())));
+        return None;
+    }
+    Some(CfuaType::Array(array))
+}
+
+fn export_table(data: &Cfua, section: Option<&str>, diagnostics: &mut Vec<CfuaError>) -> Table {
+    let mut table = Table::new();
+    for (key, value) in data {
+        let path = join(section, key);
+        if data.read_comment(key).is_some() {
+            diagnostics.push(diagnostic(&path, "comment is left out"));
+        }
+        let value = match value {
+            CfuaType::Section(inner) => Some(Value::Table(export_table(inner, Some(key), diagnostics))),
+            other => export_value(other, &path, diagnostics),
+        };
+        if let Some(value) = value {
+            table.insert(key.to_string(), value);
+        }
+    }
+    table
+}
+
+/// Converts cfua value (other than section) at `path` into TOML value.
+fn export_value(value: &CfuaType, path: &str, diagnostics: &mut Vec<CfuaError>) -> Option<Value> {
+    Some(match value {
+        CfuaType::Integer(value) => Value::Integer(*value),
+        CfuaType::Float(value) => Value::Float(*value),
+        CfuaType::String(value) => Value::String(value.clone()),
+        CfuaType::Bool(value) => Value::Boolean(*value),
+        CfuaType::Array(elements) => {
+            let elements = elements.iter().enumerate()
+                .map(|(index, element)| export_value(element, &format!("{path}[{index}]"), diagnostics))
+                .collect::<Option<_>>()?;
+            Value::Array(elements)
+        },
+        other => match other.as_integer() {
+            Some(value) => Value::Integer(value),
+            None => {
+                diagnostics.push(diagnostic(path, format!("{} value cannot be represented in TOML", other.type_name())));
+                return None;
+            },
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_toml() {
+        let input =
+r#"name = "example"
+created = 2024-01-01T00:00:00Z
+ratios = [0.5, 1.5]
+mixed = [1, "a"]
+empty = []
+nested = [[1], [2]]
+
+[Server]
+max_conns = 10
+hosts = ["alpha", "beta"]
+
+[server.tls]
+enabled = true
+
+[[users]]
+name = "root"
+"#;
+        let (data, diagnostics) = Cfua::from_toml(input).unwrap();
+
+        let expected = Cfua::from_string(
+r"name: 'example
+created: '2024-01-01T00:00:00Z
+ratios: [0.5, 1.5]
+@server
+max-conns: 10
+hosts: [
+#'alpha
+#'beta
+]
+").unwrap();
+        assert_eq!(data, expected);
+
+        let messages: Vec<_> = diagnostics.iter().map(ToString::to_string).collect();
+        assert_eq!(messages, vec![
+            "date and time converted into string (at 'created')",
+            "array mixing integer and string values cannot be represented in cfua (at 'mixed')",
+            "empty arrays cannot be represented in cfua (at 'empty')",
+            "nested arrays cannot be represented in cfua (at 'nested[0]')",
+            "key renamed to 'server' (at 'Server')",
+            "key renamed to 'max-conns' (at 'Server.max_conns')",
+            "nested tables cannot be represented in cfua (at 'server.tls')",
+            "duplicate key is left out (at 'server')",
+            "tables inside of arrays cannot be represented in cfua (at 'users[0]')",
+        ]);
+
+        assert!(Cfua::from_toml("name = ").is_err());
+    }
+
+    #[test]
+    fn write_toml() {
+        let mut data = Cfua::from_string(
+r"name: 'example
+big: 18446744073709551615
+@server
+port: 8080
+limits: [1.5, 2.0]
+").unwrap();
+        data.write_comment("name", "project name").unwrap();
+
+        let (output, diagnostics) = data.to_toml();
+        assert_eq!(output, "name = \"example\"\n\n[server]\nport = 8080\nlimits = [1.5, 2.0]\n");
+
+        let messages: Vec<_> = diagnostics.iter().map(ToString::to_string).collect();
+        assert_eq!(messages, vec![
+            "comment is left out (at 'name')",
+            "integer value cannot be represented in TOML (at 'big')",
+        ]);
+
+        let (read, diagnostics) = Cfua::from_toml(&output).unwrap();
+        assert!(diagnostics.is_empty());
+        assert_eq!(read.query("server.limits[1]").and_then(CfuaType::as_float), Some(2.0));
+    }
+}
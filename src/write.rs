@@ -1,79 +1,480 @@
-use std::ops::Index;
-
-use crate::{cfua::CfuaType, Cfua};
-
-impl ToString for Cfua {
-    /// Converts constructed data to string.
-    fn to_string(&self) -> String {
-        let mut output = String::new();
-
-        for (key, value) in self.get_all() {
-            if let CfuaType::Section(_) = value {
-                output.push('@');
-                output.push_str(key.as_str());
-            } else {
-                output.push_str(key.as_str());
-                output.push_str(": ");
-                match value {
-                    CfuaType::Integer(value) => output.push_str(value.to_string().as_str()),
-                    CfuaType::Float(value) => output.push_str(value.to_string().as_str()),
-                    CfuaType::String(value) => {
-                        let split: Vec<_> = value.split('\n').collect();
-                        for i in 0..split.len() {
-                            output.push('\'');
-                            output.push_str(split.index(i));
-                            if i + 1 != split.len() {
-                                output.push('\n');
-                            }
-                        }
-                    },
-                    CfuaType::Bool(value) => output.push_str(value.to_string().as_str()),
-                    CfuaType::Array(value) => {
-                        output.push('[');
-                        for i in 0..value.len() {
-                            match value.index(i) {
-                                CfuaType::Integer(el) => output.push_str(el.to_string().as_str()),
-                                CfuaType::Float(el) => output.push_str(el.to_string().as_str()),
-                                CfuaType::String(el) => {
-                                    if i == 0 {
-                                        output.push('\n');
-                                    }
-                                    output.push('#');
-                                    let split: Vec<_> = el.split('\n').collect();
-                                    for i in 0..split.len() {
-                                        output.push('\'');
-                                        output.push_str(split.index(i));
-                                        if i + 1 != split.len() {
-                                            output.push('\n');
-                                        }
-                                    }
-                                },
-                                CfuaType::Bool(el) => output.push_str(el.to_string().as_str()),
-                                _ => unreachable!(),
-                            }
-                            if let CfuaType::String(_) = value.index(i) {
-                                output.push('\n');
-                            } else {
-                                if i + 1 != value.len() {
-                                    output.push_str(", ");
-                                }
-                            }
-                        }
-                        output.push(']');
-                    },
-                    CfuaType::Section(_) => unreachable!(),
+use std::{fmt::{self, Write}, io};
+
+use crate::{cfua::{check_array, CfuaType}, Cfua, CfuaError};
+
+/// Numeric base of integer literals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Radix {
+    /// Written with `b` prefix, e.g. `b1010`.
+    Binary,
+    /// Written with `o` prefix, e.g. `o12`.
+    Octal,
+    #[default]
+    Decimal,
+    /// Written with `h` prefix, e.g. `ha`.
+    Hexadecimal,
+}
+
+impl Radix {
+    fn prefix(self) -> &'static str {
+        match self {
+            Radix::Binary => "b",
+            Radix::Octal => "o",
+            Radix::Decimal => "",
+            Radix::Hexadecimal => "h",
+        }
+    }
+
+    /// Formats digits of `magnitude` in this radix.
+    fn digits(self, magnitude: u64) -> String {
+        match self {
+            Radix::Binary => format!("{magnitude:b}"),
+            Radix::Octal => format!("{magnitude:o}"),
+            Radix::Decimal => magnitude.to_string(),
+            Radix::Hexadecimal => format!("{magnitude:x}"),
+        }
+    }
+}
+
+/// Layout of arrays. Arrays with strings always use `#` syntax,
+/// as strings are not allowed in simple arrays, while other arrays
+/// always use simple syntax, as only strings may follow `#`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArrayStyle {
+    /// Comma-separated elements in the same line, e.g. `[1, 2, 3]`.
+    #[default]
+    Simple,
+    /// Each element in separate line: strings preceded by `#`,
+    /// other elements followed by `,`, e.g. `[\n80,\n443]`.
+    Hash,
+}
+
+/// Options controlling layout of [`CfuaWriter`] output.
+///
+/// ## Example
+///
+/// ```
+/// use cfua::{ArrayStyle, Cfua, CfuaWriter, FormatOptions, Radix};
+///
+/// let mut data = Cfua::create();
+/// data.write("mask", 255);
+/// data.write("ports", vec![80, 443]);
+///
+/// let writer = CfuaWriter::new(FormatOptions {
+///     array_style: ArrayStyle::Hash,
+///     radix: Some(Radix::Hexadecimal),
+///     indent: 2,
+///     align_keys: true,
+///     ..Default::default()
+/// });
+/// assert_eq!(writer.format(&data).unwrap(), "mask:  hff\nports: [\n  h50,\n  h1bb]\n");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// Layout of arrays.
+    pub array_style: ArrayStyle,
+    /// Maximal width of lines with simple arrays. Elements which would
+    /// exceed it are moved to the next line. Other lines are not wrapped.
+    pub max_width: Option<usize>,
+    /// Number of spaces before array elements written in separate lines.
+    pub indent: usize,
+    /// Radix of all integers (decimal by default), or `None` to keep
+    /// radix in which they were read (decimal for values not read
+    /// from input).
+    pub radix: Option<Radix>,
+    /// Number of blank lines written before each section.
+    pub section_spacing: usize,
+    /// Whether values following each other should be aligned,
+    /// by padding keys with spaces after colon.
+    pub align_keys: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            array_style: ArrayStyle::default(),
+            max_width: None,
+            indent: 0,
+            radix: Some(Radix::Decimal),
+            section_spacing: 0,
+            align_keys: false,
+        }
+    }
+}
+
+/// Converts [`Cfua`] data into text, with layout controlled
+/// by [`FormatOptions`]. [`Display`](fmt::Display) implementation
+/// of [`Cfua`] uses default options.
+#[derive(Debug, Clone, Default)]
+pub struct CfuaWriter {
+    options: FormatOptions,
+}
+
+impl fmt::Display for Cfua {
+    /// Writes data using default [`FormatOptions`]. This is lossy: values
+    /// which cannot be written (see [`Cfua::validate`]), such as empty
+    /// arrays, are left out with their keys and comments. Use
+    /// [`CfuaWriter::format`] to get an error for them instead,
+    /// or [`CfuaWriter::format_lossy`] to learn which were left out.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        CfuaWriter::default().write_data(&mut Output::new(f), self, false, &mut |_| Ok(()))
+    }
+}
+
+impl fmt::Display for CfuaType {
+    /// Writes value in cfua syntax, as it would be written after key,
+    /// using default [`FormatOptions`]. Sections are written as their
+    /// content. Values which cannot be written, e.g. an empty array,
+    /// are written as nothing, like in [`Cfua`] output.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let writer = CfuaWriter::default();
+        let mut output = Output::new(f);
+        match self {
+            CfuaType::String(value) => writer.write_string(&mut output, value, 0),
+            CfuaType::Array(value) if check_array(value).is_err() => Ok(()),
+            CfuaType::Array(value) => writer.write_array(&mut output, value, Radix::Decimal),
+            CfuaType::Section(value) => writer.write_data(&mut output, value, true, &mut |_| Ok(())),
+            other => writer.write_scalar(&mut output, other, Radix::Decimal),
+        }
+    }
+}
+
+impl Cfua {
+    /// Writes data into `writer` using default [`FormatOptions`], without
+    /// building whole output in memory. Note that `writer` is written
+    /// in small chunks, so it should usually be buffered.
+    pub fn write_to<W>(&self, writer: W) -> io::Result<()>
+    where W: io::Write {
+        CfuaWriter::default().write_to(writer, self)
+    }
+}
+
+/// Destination of written text, which tracks position needed for layout.
+struct Output<'a> {
+    inner: &'a mut dyn Write,
+    /// number of chars written since last newline
+    column: usize,
+    /// whether anything was written yet
+    started: bool,
+}
+
+impl<'a> Output<'a> {
+    fn new(inner: &'a mut dyn Write) -> Self {
+        Self { inner, column: 0, started: false }
+    }
+}
+
+impl Write for Output<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        match s.rfind('\n') {
+            Some(i) => self.column = s[i + 1..].chars().count(),
+            None => self.column += s.chars().count(),
+        }
+        self.started |= !s.is_empty();
+        self.inner.write_str(s)
+    }
+}
+
+/// Adapts [`io::Write`] for writing formatted text, keeping error
+/// which can't be passed through [`fmt::Error`].
+struct IoOutput<W> {
+    inner: W,
+    error: Option<io::Error>,
+}
+
+impl<W> Write for IoOutput<W>
+where W: io::Write {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
+        })
+    }
+}
+
+/// Returns error of value which could not be written, recorded while
+/// writing failed, or generic error if output failed by itself.
+fn take_error(error: Option<CfuaError>) -> CfuaError {
+    error.unwrap_or_else(|| CfuaError::Conversion {
+        key: String::new(),
+        message: "formatting failed".to_string(),
+    })
+}
+
+/// Writes float so that it's read back as the same float: with decimal
+/// point (`1.0` rather than `1`) and special values as keywords.
+/// Rust's formatting already produces the shortest exact representation.
+fn write_float(output: &mut impl Write, value: f64) -> fmt::Result {
+    if value.is_nan() {
+        output.write_str("nan")
+    } else if value.is_infinite() {
+        output.write_str(if value > 0.0 { "inf" } else { "-inf" })
+    } else {
+        let text = value.to_string();
+        output.write_str(&text)?;
+        if text.contains('.') {
+            Ok(())
+        } else {
+            output.write_str(".0")
+        }
+    }
+}
+
+/// Writes each line of `comment` as separate `%` line.
+fn write_comment(output: &mut impl Write, comment: &str) -> fmt::Result {
+    for line in comment.split('\n') {
+        if line.is_empty() {
+            output.write_str("%\n")?;
+        } else {
+            writeln!(output, "% {line}")?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes `count` spaces or newlines.
+fn repeat(output: &mut impl Write, char: char, count: usize) -> fmt::Result {
+    (0..count).try_for_each(|_| output.write_char(char))
+}
+
+impl CfuaWriter {
+    /// Creates writer using given `options`.
+    pub fn new(options: FormatOptions) -> Self {
+        Self { options }
+    }
+
+    /// Returns options used by this writer.
+    pub fn options(&self) -> &FormatOptions {
+        &self.options
+    }
+
+    /// Converts `data` into text. Returns [`CfuaError::Conversion`]
+    /// with path of the first value which cannot be written, such as
+    /// an empty array (see [`Cfua::validate`]).
+    pub fn format(&self, data: &Cfua) -> Result<String, CfuaError> {
+        let mut text = String::new();
+        let mut error = None;
+        let result = self.write_data(&mut Output::new(&mut text), data, false, &mut |e| {
+            error = Some(e);
+            Err(fmt::Error)
+        });
+        match result {
+            Ok(()) => Ok(text),
+            Err(fmt::Error) => Err(take_error(error)),
+        }
+    }
+
+    /// Converts `data` into text, leaving out values which cannot
+    /// be written with their keys and comments. Returns the text along
+    /// with errors which [`format`](Self::format) would return for
+    /// values which were left out.
+    pub fn format_lossy(&self, data: &Cfua) -> (String, Vec<CfuaError>) {
+        let mut text = String::new();
+        let mut left_out = Vec::new();
+        // writing into `String` fails only if value which cannot be
+        // written makes it fail, which it doesn't here
+        let _ = self.write_data(&mut Output::new(&mut text), data, false, &mut |error| {
+            left_out.push(error);
+            Ok(())
+        });
+        (text, left_out)
+    }
+
+    /// Writes `data` as text into `output`, e.g. a [`fmt::Formatter`].
+    /// Fails if `output` fails, or if `data` holds a value which cannot
+    /// be written.
+    pub fn write<W>(&self, output: &mut W, data: &Cfua) -> fmt::Result
+    where W: Write {
+        self.write_data(&mut Output::new(output), data, false, &mut |_| Err(fmt::Error))
+    }
+
+    /// Writes `data` into `writer`, without building whole output
+    /// in memory. Note that `writer` is written in small chunks,
+    /// so it should usually be buffered. Values which cannot be written
+    /// are reported as [`io::ErrorKind::InvalidData`], wrapping
+    /// [`CfuaError`].
+    pub fn write_to<W>(&self, writer: W, data: &Cfua) -> io::Result<()>
+    where W: io::Write {
+        let mut io_output = IoOutput { inner: writer, error: None };
+        let mut error = None;
+        let result = self.write_data(&mut Output::new(&mut io_output), data, false, &mut |e| {
+            error = Some(e);
+            Err(fmt::Error)
+        });
+        match result {
+            Ok(()) => Ok(()),
+            Err(fmt::Error) => Err(io_output.error
+                .unwrap_or_else(|| io::Error::new(io::ErrorKind::InvalidData, take_error(error)))),
+        }
+    }
+
+    /// Writes all values from `data`, followed by its sections. Sections
+    /// in `data` which is content of a section (`in_section`) cannot
+    /// be written, as these cannot be nested. Error of each value which
+    /// cannot be written is passed to `unwritable`, which either fails,
+    /// or lets the value be left out with its key and comment.
+    fn write_data(&self, output: &mut Output<'_>, data: &Cfua, in_section: bool, unwritable: &mut dyn FnMut(CfuaError) -> fmt::Result) -> fmt::Result {
+        let (sections, values): (Vec<_>, Vec<_>) = data.entries().iter().enumerate()
+            .partition(|(_, (_, value))| matches!(value, CfuaType::Section(_)));
+
+        let key_width = if self.options.align_keys {
+            values.iter().map(|(_, (key, _))| key.chars().count()).max().unwrap_or(0)
+        } else {
+            0
+        };
+        for (i, (key, value)) in values {
+            if let CfuaType::Array(elements) = value
+                && let Err(error) = check_array(elements) {
+                unwritable(error.at_key(key))?;
+                continue;
+            }
+            if let Some(comment) = data.comment_at(i) {
+                write_comment(output, comment)?;
+            }
+            self.write_entry(output, key, value, data.radix_at(i), key_width)?;
+        }
+
+        for (i, (key, value)) in sections {
+            if let CfuaType::Section(section) = value {
+                if in_section {
+                    unwritable(CfuaError::NestedSection(key.clone()))?;
+                    continue;
                 }
+                if output.started {
+                    repeat(output, '\n', self.options.section_spacing)?;
+                }
+                if let Some(comment) = data.comment_at(i) {
+                    write_comment(output, comment)?;
+                }
+                writeln!(output, "@{key}")?;
+                self.write_data(output, section, true, &mut |error| unwritable(error.at_key(key)))?;
             }
-            output.push_str("\n");
         }
+        Ok(())
+    }
+
+    /// Writes single non-section `value` with its `key`, followed by newline.
+    /// Integers are written in `radix`, unless other is forced by options.
+    /// Value is preceded by enough spaces to align it with keys
+    /// of `key_width` chars. Arrays must be checked with `check_array`
+    /// before, as this writes them even if parser would reject them.
+    fn write_entry(&self, output: &mut Output<'_>, key: &str, value: &CfuaType, radix: Radix, key_width: usize) -> fmt::Result {
+        let radix = self.options.radix.unwrap_or(radix);
+        output.write_str(key)?;
+        output.write_char(':')?;
+        repeat(output, ' ', key_width.saturating_sub(key.chars().count()) + 1)?;
+
+        match value {
+            CfuaType::String(value) => self.write_string(output, value, 0)?,
+            CfuaType::Array(value) => self.write_array(output, value, radix)?,
+            CfuaType::Section(_) => unreachable!(),
+            other => self.write_scalar(output, other, radix)?,
+        }
+        output.write_char('\n')
+    }
+
+    /// Formats single non-section `value` with its `key` as a line
+    /// (or lines) of text, ending with newline. Fails if `value`
+    /// is an array which cannot be written.
+    pub(crate) fn format_entry(&self, key: &str, value: &CfuaType) -> Result<String, CfuaError> {
+        if let CfuaType::Array(elements) = value {
+            check_array(elements).map_err(|e| e.at_key(key))?;
+        }
+        let mut text = String::new();
+        // writing checked value into `String` cannot fail
+        let _ = self.write_entry(&mut Output::new(&mut text), key, value, Radix::Decimal, 0);
+        Ok(text)
+    }
+
+    /// Writes value which is neither a string, array nor section.
+    fn write_scalar(&self, output: &mut impl Write, value: &CfuaType, radix: Radix) -> fmt::Result {
+        let (negative, digits) = match value {
+            CfuaType::Integer(value) => (*value < 0, radix.digits(value.unsigned_abs())),
+            CfuaType::Unsigned(value) => (false, radix.digits(*value)),
+            #[cfg(feature = "bigint")]
+            CfuaType::BigInt(value) => {
+                let base = match radix {
+                    Radix::Binary => 2,
+                    Radix::Octal => 8,
+                    Radix::Decimal => 10,
+                    Radix::Hexadecimal => 16,
+                };
+                (value.sign() == num_bigint::Sign::Minus, value.magnitude().to_str_radix(base))
+            },
+            CfuaType::Float(value) => return write_float(output, *value),
+            CfuaType::Bool(value) => return write!(output, "{value}"),
+            _ => unreachable!(),
+        };
+
+        let sign = if negative { "-" } else { "" };
+        write!(output, "{sign}{}{digits}", radix.prefix())
+    }
 
-        output
+    /// Writes string, with its following lines indented by `indent` spaces.
+    fn write_string(&self, output: &mut impl Write, value: &str, indent: usize) -> fmt::Result {
+        for (i, line) in value.split('\n').enumerate() {
+            if i > 0 {
+                output.write_char('\n')?;
+                repeat(output, ' ', indent)?;
+            }
+            write!(output, "'{line}")?;
+        }
+        Ok(())
+    }
+
+    /// Writes array of scalars of the same type, using `#` syntax
+    /// for strings, and simple syntax for other elements.
+    fn write_array(&self, output: &mut Output<'_>, elements: &[CfuaType], radix: Radix) -> fmt::Result {
+        let indent = self.options.indent;
+        let separate_lines = self.options.array_style == ArrayStyle::Hash;
+
+        output.write_char('[')?;
+        if elements.iter().any(|el| matches!(el, CfuaType::String(_))) {
+            output.write_char('\n')?;
+            for el in elements {
+                if let CfuaType::String(el) = el {
+                    repeat(output, ' ', indent)?;
+                    output.write_char('#')?;
+                    self.write_string(output, el, indent)?;
+                    output.write_char('\n')?;
+                }
+            }
+        } else {
+            if separate_lines {
+                output.write_char('\n')?;
+                repeat(output, ' ', indent)?;
+            }
+            let mut element = String::new();
+            for (i, el) in elements.iter().enumerate() {
+                element.clear();
+                self.write_scalar(&mut element, el, radix)?;
+
+                if i > 0 && separate_lines {
+                    output.write_str(",\n")?;
+                    repeat(output, ' ', indent)?;
+                } else if i > 0 {
+                    output.write_char(',')?;
+                    // element is followed by either `,` or `]`
+                    let width = output.column + 1 + element.chars().count() + 1;
+                    if self.options.max_width.is_some_and(|max| width > max) {
+                        output.write_char('\n')?;
+                        repeat(output, ' ', indent)?;
+                    } else {
+                        output.write_char(' ')?;
+                    }
+                }
+                output.write_str(&element)?;
+            }
+        }
+        output.write_char(']')
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{Cfua, array::{CfuaIntegerArray, CfuaStringArray}};
+    use proptest::prelude::*;
+
+    use crate::{ArrayStyle, Cfua, CfuaError, CfuaType, CfuaWriter, FormatOptions, Radix, array::{CfuaIntegerArray, CfuaStringArray}};
 
     #[test]
     fn to_string_basic() {
@@ -130,4 +531,359 @@ split-array: [
 ".to_string();
         assert_eq!(structure.to_string(), example)
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn to_string_sections() {
+        let mut server = Cfua::create();
+        server.write_integer("port", 8080);
+        let mut database = Cfua::create();
+        database.write_integer("port", 5432);
+
+        let mut structure = Cfua::create();
+        structure.write_section("server", server).unwrap();
+        structure.write_section("database", database).unwrap();
+        structure.write_string("name", "example");
+
+        let example =
+r"name: 'example
+@server
+port: 8080
+@database
+port: 5432
+".to_string();
+        assert_eq!(structure.to_string(), example);
+    }
+
+    #[test]
+    fn nested_sections_are_rejected() {
+        let mut inner = Cfua::create();
+        inner.write_integer("port", 8080);
+        let mut outer = Cfua::create();
+        outer.write_section("inner", inner).unwrap();
+
+        let mut structure = Cfua::create();
+        let error = structure.write_section("outer", outer).unwrap_err();
+        assert!(matches!(&error, CfuaError::NestedSection(name) if name == "inner"));
+        assert!(structure.sections().is_empty());
+    }
+
+    #[test]
+    fn to_string_comments() {
+        let mut structure = Cfua::create();
+        structure.write_integer("port", 8080);
+        structure.write_comment("port", "Port to listen on.\n\nDefaults to 8080.").unwrap();
+        structure.write_section("server", Cfua::create()).unwrap();
+        structure.write_comment("server", "Server settings").unwrap();
+
+        let example =
+r"% Port to listen on.
+%
+% Defaults to 8080.
+port: 8080
+% Server settings
+@server
+".to_string();
+        assert_eq!(structure.to_string(), example);
+
+        let mut parsed = Cfua::from_string(&example).unwrap();
+        assert_eq!(parsed.read_comment("port"), Some("Port to listen on.\n\nDefaults to 8080."));
+        assert_eq!(parsed.read_comment("server"), Some("Server settings"));
+        assert_eq!(parsed.to_string(), example);
+
+        parsed.rename("port", "listen").unwrap();
+        assert_eq!(parsed.read_comment("listen"), Some("Port to listen on.\n\nDefaults to 8080."));
+        parsed.remove("listen");
+        assert_eq!(parsed.read_comment("listen"), None);
+    }
+
+    #[test]
+    fn comments_after_multiline_strings() {
+        let example =
+r"a: 'x
+% inside a
+'y
+% about b
+b: 2
+% about c
+c: 'z
+% about server
+@server
+";
+        let parsed = Cfua::from_string(example).unwrap();
+        assert_eq!(parsed.read_string("a").as_deref(), Some("x\ny"));
+        assert_eq!(parsed.read_comment("a"), None);
+        assert_eq!(parsed.read_comment("b"), Some("about b"));
+        assert_eq!(parsed.read_comment("c"), Some("about c"));
+        assert_eq!(parsed.read_comment("server"), Some("about server"));
+        assert_eq!(parsed.to_string(), example.replace("% inside a\n", ""));
+    }
+
+    #[test]
+    fn comments_follow_entries() {
+        let example =
+r"% first
+name: 'a
+% second
+name: 'b
+% about value
+server: 1
+% about section
+@server
+";
+        let mut parsed = Cfua::from_string(example).unwrap();
+        assert_eq!(parsed.read_comment("name"), Some("first"));
+        assert_eq!(parsed.read_comment("server"), Some("about value"));
+        assert_eq!(parsed.to_string(), example);
+
+        parsed.remove_at(0);
+        assert_eq!(parsed.read_comment("name"), Some("second"));
+        assert!(matches!(parsed.write_comment("port", "x"), Err(CfuaError::KeyNotFound(key)) if key == "port"));
+    }
+
+    #[test]
+    fn to_string_floats() {
+        let mut structure = Cfua::create();
+        structure.write_float("whole", 1.0);
+        structure.write_float("negative", -0.0);
+        structure.write_float("huge", 1e20);
+        structure.write_float("not-a-number", f64::NAN);
+        structure.write("limits", vec![f64::INFINITY, f64::NEG_INFINITY, f64::NAN, 0.1]).unwrap();
+
+        let example =
+r"whole: 1.0
+negative: -0.0
+huge: 100000000000000000000.0
+not-a-number: nan
+limits: [inf, -inf, nan, 0.1]
+".to_string();
+        assert_eq!(structure.to_string(), example);
+
+        let parsed = Cfua::from_string(&example).unwrap();
+        assert_eq!(parsed.read_value("whole"), Some(&CfuaType::Float(1.0)));
+        assert!(parsed.read_float("negative").unwrap().is_sign_negative());
+        assert!(parsed.read_float("not-a-number").unwrap().is_nan());
+        assert_eq!(parsed.to_string(), example);
+    }
+
+    #[test]
+    fn empty_strings_in_arrays() {
+        let mut structure = Cfua::create();
+        structure.write("first", vec!["", "x"]).unwrap();
+        structure.write("last", vec!["x", ""]).unwrap();
+        structure.write("lines", vec!["\nx\n"]).unwrap();
+
+        let example =
+r"first: [
+#'
+#'x
+]
+last: [
+#'x
+#'
+]
+lines: [
+#'
+'x
+'
+]
+";
+        assert_eq!(structure.to_string(), example);
+        assert_eq!(Cfua::from_string(example).unwrap(), structure);
+    }
+
+    /// Checks that `read` is the same float as `value`, treating all NaNs as equal.
+    fn same_float(read: Option<f64>, value: f64) -> bool {
+        read.is_some_and(|read| read.to_bits() == value.to_bits() || (read.is_nan() && value.is_nan()))
+    }
+
+    proptest! {
+        #[test]
+        fn floats_round_trip(value in proptest::num::f64::ANY, element in proptest::num::f64::ANY) {
+            let mut structure = Cfua::create();
+            structure.write_float("value", value);
+            structure.write("array", vec![element, value]).unwrap();
+
+            let parsed = Cfua::from_string(structure.to_string()).unwrap();
+            prop_assert!(same_float(parsed.read_float("value"), value));
+            let array = parsed.get::<Vec<f64>>("array").unwrap();
+            prop_assert!(same_float(Some(array[0]), element));
+            prop_assert!(same_float(Some(array[1]), value));
+        }
+    }
+
+    #[test]
+    fn format_options() {
+        let example =
+r"mask: b1010
+% listening ports
+ports: [h50, h1bb, -h1]
+names: [
+#'multi
+'line
+#'single
+]
+@server
+timeout: 1.5
+limits: [0.5,
+2.0]
+";
+        let data = Cfua::from_string(example).unwrap();
+        assert_eq!(data.query("server.limits[1]"), Some(&CfuaType::Float(2.0)));
+        assert_eq!(data.to_string(), example
+            .replace("b1010", "10")
+            .replace("[h50, h1bb, -h1]", "[80, 443, -1]")
+            .replace("[0.5,\n2.0]", "[0.5, 2.0]"));
+
+        let preserving = CfuaWriter::new(FormatOptions { radix: None, ..Default::default() });
+        assert_eq!(preserving.format(&data).unwrap(), example.replace("[0.5,\n2.0]", "[0.5, 2.0]"));
+
+        let writer = CfuaWriter::new(FormatOptions {
+            array_style: ArrayStyle::Hash,
+            indent: 2,
+            section_spacing: 1,
+            align_keys: true,
+            ..Default::default()
+        });
+        let expected =
+r"mask:  10
+% listening ports
+ports: [
+  80,
+  443,
+  -1]
+names: [
+  #'multi
+  'line
+  #'single
+]
+
+@server
+timeout: 1.5
+limits:  [
+  0.5,
+  2.0]
+";
+        assert_eq!(writer.format(&data).unwrap(), expected);
+        assert_eq!(Cfua::from_string(expected).unwrap(), data);
+    }
+
+    #[test]
+    fn array_wrapping() {
+        let mut structure = Cfua::create();
+        structure.write("primes", vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]).unwrap();
+
+        let writer = CfuaWriter::new(FormatOptions {
+            max_width: Some(20),
+            indent: 4,
+            radix: Some(Radix::Hexadecimal),
+            ..Default::default()
+        });
+        let expected =
+r"primes: [h2, h3, h5,
+    h7, hb, hd, h11,
+    h13, h17, h1d]
+";
+        assert_eq!(writer.format(&structure).unwrap(), expected);
+        assert_eq!(Cfua::from_string(expected).unwrap(), structure);
+    }
+
+    #[test]
+    fn write_to_streams() {
+        let mut structure = Cfua::create();
+        structure.write_integer("port", 8080);
+        structure.write_section("empty", Cfua::create()).unwrap();
+
+        let mut bytes = Vec::new();
+        structure.write_to(&mut bytes).unwrap();
+        assert_eq!(bytes, b"port: 8080\n@empty\n");
+        assert_eq!(format!("{structure}"), "port: 8080\n@empty\n");
+
+        struct Broken;
+        impl std::io::Write for Broken {
+            fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("disk full"))
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        assert_eq!(structure.write_to(Broken).unwrap_err().to_string(), "disk full");
+    }
+
+    #[test]
+    fn unwritable_values() {
+        let mut section = Cfua::create();
+        section.write_value("ports", CfuaType::Array(Vec::new()));
+        let mut structure = Cfua::create();
+        structure.write_section("server", section).unwrap();
+        let error = CfuaWriter::default().format(&structure).unwrap_err();
+        assert_eq!(error.to_string(), "empty array cannot be written (at 'server.ports')");
+        let error = structure.write_to(Vec::new()).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(error.to_string(), "empty array cannot be written (at 'server.ports')");
+
+        let mut inner = Cfua::create();
+        inner.write_integer("a", 1);
+        let mut middle = Cfua::create();
+        middle.write_section("inner", inner).unwrap();
+        let mut structure = Cfua::create();
+        structure.write_value("mid", CfuaType::Section(middle));
+        let error = CfuaWriter::default().format(&structure).unwrap_err();
+        assert!(matches!(error, CfuaError::NestedSection(name) if name == "inner"));
+
+        let mut structure = Cfua::create();
+        structure.write_value("value", CfuaType::Array(vec![CfuaType::Integer(1), CfuaType::Float(2.5)]));
+        let error = CfuaWriter::default().format(&structure).unwrap_err();
+        assert_eq!(error.to_string(), "array mixing integer and float values cannot be written (at 'value')");
+    }
+
+    #[test]
+    fn display_leaves_out_unwritable_values() {
+        let mut inner = Cfua::create();
+        inner.write_integer("a", 1);
+        let mut nested = Cfua::create();
+        nested.write_integer("b", 2);
+        nested.write_section("inner", inner).unwrap();
+
+        let rejected = [
+            CfuaType::Array(Vec::new()),
+            CfuaType::Array(vec![CfuaType::Array(vec![CfuaType::Integer(1)])]),
+            CfuaType::Array(vec![CfuaType::Section(Cfua::create())]),
+            CfuaType::Array(vec![CfuaType::Integer(1), CfuaType::Float(2.5)]),
+            CfuaType::Array(vec![CfuaType::String("x".to_string()), CfuaType::Bool(true)]),
+        ];
+        for value in rejected {
+            let mut structure = Cfua::create();
+            structure.write_integer("before", 1);
+            structure.write_value("value", value.clone());
+            structure.write_comment("value", "left out too").unwrap();
+            structure.write_integer("after", 2);
+
+            assert!(structure.validate().is_err());
+            assert_eq!(structure.to_string(), "before: 1\nafter: 2\n");
+            let (text, left_out) = CfuaWriter::default().format_lossy(&structure);
+            assert_eq!(text, structure.to_string());
+            assert!(matches!(&left_out[..], [CfuaError::Conversion { key, .. }] if key == "value"));
+            assert_eq!(value.to_string(), "");
+        }
+
+        let mut structure = Cfua::create();
+        structure.write_value("outer", CfuaType::Section(nested.clone()));
+        structure.write_value("empty", CfuaType::Array(Vec::new()));
+        assert!(structure.validate().is_err());
+        assert_eq!(structure.to_string(), "@outer\nb: 2\n");
+        let (_, left_out) = CfuaWriter::default().format_lossy(&structure);
+        assert!(matches!(&left_out[..], [CfuaError::Conversion { key, .. }, CfuaError::NestedSection(name)]
+            if key == "empty" && name == "inner"));
+        assert_eq!(CfuaType::Section(nested).to_string(), "b: 2\n");
+    }
+
+    #[test]
+    fn display_values() {
+        assert_eq!(CfuaType::Float(2.0).to_string(), "2.0");
+        assert_eq!(CfuaType::String("a\nb".to_string()).to_string(), "'a\n'b");
+        assert_eq!(CfuaType::Array(vec![CfuaType::Bool(false), CfuaType::Bool(true)]).to_string(), "[false, true]");
+        assert_eq!(CfuaType::Section(Cfua::from_string("x: 1\n").unwrap()).to_string(), "x: 1\n");
+    }
+}
+
@@ -0,0 +1,64 @@
+//! Tests of `cfua` command-line tool, checking its exit codes
+//! and effect on files.
+
+use std::{env, fs, path::PathBuf, process::{Command, Output}};
+
+/// Writes `content` into a file unique for `name`, returning its path.
+fn file(name: &str, content: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("cfua-cli-{}-{name}.cfua", std::process::id()));
+    fs::write(&path, content).unwrap();
+    path
+}
+
+fn cfua(args: &[&str], file: &PathBuf) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_cfua")).args(args).arg(file).output().unwrap()
+}
+
+#[test]
+fn check_exit_codes() {
+    let valid = file("check-valid", "name: 'x\n");
+    assert!(cfua(&["check"], &valid).status.success());
+
+    let invalid = file("check-invalid", "name: x\nport: 1a\n");
+    let output = cfua(&["check"], &invalid);
+    assert_eq!(output.status.code(), Some(1));
+    let errors = String::from_utf8_lossy(&output.stderr);
+    assert!(errors.contains("unknown keyword") && errors.contains("malformed integer"));
+
+    fs::remove_file(valid).unwrap();
+    fs::remove_file(invalid).unwrap();
+}
+
+#[test]
+fn fmt_check_exit_codes() {
+    let formatted = file("fmt-formatted", "% name\nname: 'x\n");
+    assert!(cfua(&["fmt", "--check"], &formatted).status.success());
+
+    let unformatted = file("fmt-unformatted", "@server\nport: h50\n");
+    let output = cfua(&["fmt", "--check"], &unformatted);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("fmt-unformatted"));
+    assert_eq!(fs::read_to_string(&unformatted).unwrap(), "@server\nport: h50\n");
+
+    assert!(cfua(&["fmt"], &unformatted).status.success());
+    assert_eq!(fs::read_to_string(&unformatted).unwrap(), "@server\nport: 80\n");
+    assert!(cfua(&["fmt", "--check"], &unformatted).status.success());
+
+    fs::remove_file(formatted).unwrap();
+    fs::remove_file(unformatted).unwrap();
+}
+
+#[test]
+fn fmt_keeps_unattached_comments() {
+    let input = "% header\n\nname: 'x\n% trailing\n";
+    let path = file("fmt-comments", input);
+
+    for args in [&["fmt"][..], &["fmt", "--check"]] {
+        let output = cfua(args, &path);
+        assert_eq!(output.status.code(), Some(1));
+        assert!(String::from_utf8_lossy(&output.stderr).contains("would remove comments"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), input);
+    }
+
+    fs::remove_file(path).unwrap();
+}